@@ -11,14 +11,33 @@ use tempdir::TempDir;
 
 pub type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
-pub fn regs_asl_path() -> PathBuf {
+fn data_path(name: &str) -> PathBuf {
     let path = dirs::data_dir().expect("Can't get user data directory");
 
     let config_dir = path.join("asl_parser");
 
     fs::create_dir_all(&config_dir).expect("Can't create app data directory");
 
-    config_dir.join("regs.asl")
+    config_dir.join(name)
+}
+
+pub fn regs_asl_path() -> PathBuf {
+    data_path("regs.asl")
+}
+
+/// Optional user metadata extending the built-in one
+pub fn regs_meta_path() -> PathBuf {
+    data_path("regs.meta")
+}
+
+/// Interactive prompt history
+pub fn history_path() -> PathBuf {
+    data_path("history")
+}
+
+/// Optional user definitions of IMP DEF registers in ASL syntax
+pub fn impdef_asl_path() -> PathBuf {
+    data_path("impdef.asl")
 }
 
 fn run_make(dir: &Path, target: &str) -> Result<()> {
@@ -41,6 +60,7 @@ async fn download_file(from: String, to: PathBuf) -> Result<()> {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(true)
         .open(&to)?;
 
     let mut content = Cursor::new(response.bytes().await?);
@@ -23,3 +23,60 @@ pub struct Range {
     pub from: u32,
     pub to: u32,
 }
+
+pub enum Meta<'a> {
+    /// Named values of a field
+    Values(FieldRef<'a>, Vec<FieldValue<'a>>),
+    /// Field description text
+    Describe(FieldRef<'a>, &'a str),
+    /// Attributes of bit ranges of a register
+    Attrs(&'a str, Vec<AttrRange>),
+    /// Encoding of MRS/MSR or, with a coprocessor name, MRC/MCR operands
+    Encoding(&'a str, Option<&'a str>, Vec<u64>),
+    /// Alternative layout of a field or bit range
+    Variant(Variant<'a>),
+    /// Features required by a register or its field
+    Features(FeaturesTarget<'a>, Vec<&'a str>),
+}
+
+pub enum FeaturesTarget<'a> {
+    Register(&'a str),
+    Field(FieldRef<'a>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VariantTarget<'a> {
+    Field(FieldRef<'a>),
+    /// Register name and `to:from` bits
+    Range(&'a str, u32, u32),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Variant<'a> {
+    pub target: VariantTarget<'a>,
+    pub discriminator: &'a str,
+    pub values: Vec<u64>,
+    pub name: &'a str,
+    pub fields: Vec<Bitfield<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FieldRef<'a> {
+    pub register: &'a str,
+    /// Layout variant defining the field, `REG["Data Abort"].FIELD`
+    pub variant: Option<&'a str>,
+    pub field: &'a str,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FieldValue<'a> {
+    pub value: u64,
+    pub meaning: &'a str,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AttrRange {
+    pub from: u32,
+    pub to: u32,
+    pub attr: crate::FieldAttr,
+}
@@ -0,0 +1,199 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde_json::json;
+
+use crate::{
+    encoding::find_register,
+    expr::parse_number,
+    render::{render, RenderOptions},
+    report::{self, register_json, registers_csv},
+    RegisterDesc,
+};
+
+/// Register value decoded from a dump line
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Line number, starting from 1
+    pub line: usize,
+    pub reg: RegisterDesc,
+}
+
+/// Dump line that couldn't be decoded
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Register values decoded from a dump, problems with single lines are
+/// collected as diagnostics
+#[derive(Debug, Clone, Default)]
+pub struct Dump {
+    pub entries: Vec<Entry>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// `name=value` or `name:` followed by value in `tokens[i]`, `name` must
+/// start with a letter
+fn assignment_at<'a>(tokens: &[&'a str], i: usize) -> Option<(&'a str, &'a str)> {
+    let (name, value) = match tokens[i].split_once(['=', ':']) {
+        Some((name, "")) => (name, *tokens.get(i + 1)?),
+        Some(x) => x,
+        /* `name = value` */
+        None if ["=", ":"].contains(tokens.get(i + 1)?) => (tokens[i], *tokens.get(i + 2)?),
+        None => return None,
+    };
+    Some((name, value)).filter(|(name, _)| name.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+/// Split `name=value`, `name: value` or `name value` line. Log prefixes
+/// are skipped, the last `name=value` or `name: value` of the line is taken.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let tokens: Vec<_> = line.split_whitespace().collect();
+    let found = (0..tokens.len())
+        .rev()
+        .find_map(|i| assignment_at(&tokens, i));
+    let (name, value) = match found {
+        Some(x) => x,
+        None if tokens.len() >= 2 => (tokens[tokens.len() - 2], tokens[tokens.len() - 1]),
+        None => return None,
+    };
+    Some((name, value)).filter(|(name, value)| !name.is_empty() && !value.is_empty())
+}
+
+/// Decode `name=value`, `name: value` or `name value` lines. Values without
+/// `0x`, `0o` or `0b` prefix are read in `radix`. Empty lines and lines
+/// starting with `#` or `//` are skipped.
+pub fn decode_dump(data: &BTreeMap<String, RegisterDesc>, input: &str, radix: u32) -> Dump {
+    let mut dump = Dump::default();
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        let decoded = split_line(line)
+            .ok_or_else(|| format!("Expected register name and value, got {}", line))
+            .and_then(|(name, value)| {
+                let reg = find_register(data, name)
+                    .ok_or_else(|| format!("Unknown register: {}", name))?;
+                Ok(RegisterDesc {
+                    value: Some(parse_number(value, radix)?),
+                    ..reg.clone()
+                })
+            });
+
+        match decoded {
+            Ok(reg) => dump.entries.push(Entry { line: i + 1, reg }),
+            Err(message) => dump.diagnostics.push(Diagnostic {
+                line: i + 1,
+                message,
+            }),
+        }
+    }
+    dump
+}
+
+impl Dump {
+    /// Decoded registers in `format`. JSON report includes diagnostics,
+    /// text and CSV leave them to the caller.
+    pub fn render(&self, format: report::Format, opts: &RenderOptions) -> String {
+        match format {
+            report::Format::Text => {
+                let regs: Vec<_> = self.entries.iter().map(|x| render(&x.reg, opts)).collect();
+                regs.join("\n")
+            }
+            report::Format::Json => {
+                let registers: Vec<_> = self
+                    .entries
+                    .iter()
+                    .map(|x| {
+                        let mut desc = register_json(&x.reg);
+                        desc["line"] = json!(x.line);
+                        desc
+                    })
+                    .collect();
+                let diagnostics: Vec<_> = self
+                    .diagnostics
+                    .iter()
+                    .map(|x| json!({ "line": x.line, "message": x.message }))
+                    .collect();
+                let report = json!({ "registers": registers, "diagnostics": diagnostics });
+                serde_json::to_string_pretty(&report).expect("Can't serialize dump report") + "\n"
+            }
+            report::Format::Csv => {
+                let regs: Vec<_> = self.entries.iter().map(|x| x.reg.clone()).collect();
+                registers_csv(&regs)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{batch::decode_dump, parse_registers, report};
+
+    #[test]
+    fn test_decode_dump() {
+        let data = parse_registers(
+            "__register 64 { 2:2 C, 0:0 M } SCTLR_EL1;
+             __register 64 { 15:14 TG0 } TCR_EL1;",
+        );
+        let dump = decode_dump(
+            &data,
+            "# boot log
+             SCTLR_EL1=0x30d00805
+             tcr_el1: 0b1_0000_0000_0000_0000
+
+             SCTLR_EL1 5
+             SCTLR_EL1 10000000
+             NOTICE: TCR_EL1=0x8000
+             [0.000000] cpu0 SCTLR_EL1 = 30d00805
+             FOO_EL1=1
+             TCR_EL1=0xzz
+             garbage",
+            16,
+        );
+
+        let values: Vec<_> = dump.entries.iter().map(|x| x.reg.value).collect();
+        assert_eq!(
+            values,
+            [
+                Some(0x30d00805),
+                Some(0x10000),
+                Some(5),
+                Some(0x10000000),
+                Some(0x8000),
+                Some(0x30d00805),
+            ]
+        );
+        assert_eq!(dump.entries[1].line, 3);
+        assert_eq!(dump.entries[4].reg.name, "TCR_EL1");
+
+        let diagnostics: Vec<_> = dump.diagnostics.iter().map(|x| x.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            [
+                "line 9: Unknown register: FOO_EL1",
+                "line 10: Invalid number 0xzz: Number has invalid digits",
+                "line 11: Expected register name and value, got garbage",
+            ]
+        );
+
+        /* Unprefixed values are decimal on request */
+        let decimal = decode_dump(&data, "SCTLR_EL1 10000000", 10);
+        assert_eq!(decimal.entries[0].reg.value, Some(10000000));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&dump.render(report::Format::Json, &Default::default())).unwrap();
+        assert_eq!(json["registers"][2]["value"], "0x5");
+        assert_eq!(json["diagnostics"][0]["line"], 9);
+    }
+}
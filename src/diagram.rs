@@ -0,0 +1,200 @@
+use std::fmt::{self, Write};
+
+use serde_json::json;
+
+use crate::RegisterDesc;
+
+/// Number of bits drawn in a single row of a diagram
+pub const LANE_BITS: u32 = 32;
+
+const CELL_WIDTH: u32 = 28;
+const CELL_HEIGHT: u32 = 32;
+const LABEL_HEIGHT: u32 = 16;
+const MARGIN: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Svg,
+    WaveDrom,
+}
+
+/// Render register as WaveDrom `reg` JSON, with register value shown as
+/// field attributes when present
+pub fn to_wavedrom(reg: &RegisterDesc) -> String {
+    let fields: Vec<_> = reg
+        .split_lanes(LANE_BITS)
+        .iter()
+        .flat_map(|lane| lane.iter().rev())
+        .map(|field| {
+            let mut desc = json!({ "bits": field.width() });
+
+            if !field.is_padding() {
+                desc["name"] = json!(field.name);
+            }
+            if let Some(value) = reg.value {
+                desc["attr"] = json!(field.extract(value).to_string());
+            }
+            desc
+        })
+        .collect();
+
+    let diagram = json!({
+        "reg": fields,
+        "config": {
+            "bits": reg.bits,
+            "lanes": reg.bits.div_ceil(LANE_BITS).max(1),
+        },
+    });
+
+    serde_json::to_string_pretty(&diagram).expect("Can't serialize WaveDrom diagram")
+}
+
+fn write_text(out: &mut String, x: u32, y: u32, text: &str, class: &str) -> fmt::Result {
+    writeln!(
+        out,
+        r#"  <text x="{}" y="{}" class="{}">{}</text>"#,
+        x, y, class, text
+    )
+}
+
+fn write_svg(out: &mut String, reg: &RegisterDesc) -> fmt::Result {
+    let lanes = reg.split_lanes(LANE_BITS);
+    let lane_bits = reg.bits.clamp(1, LANE_BITS);
+    let rows = if reg.value.is_some() { 2 } else { 1 };
+    let lane_height = LABEL_HEIGHT + CELL_HEIGHT * rows + MARGIN;
+
+    let width = lane_bits * CELL_WIDTH + 2 * MARGIN;
+    let height = LABEL_HEIGHT + lane_height * lanes.len() as u32 + MARGIN;
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    )?;
+    writeln!(
+        out,
+        "  <style>text {{ font: 12px monospace; text-anchor: middle; dominant-baseline: middle; }} \
+         .bits {{ font-size: 10px; fill: #666; }} \
+         rect {{ stroke: #000; fill: #fff; }} rect.padding {{ fill: #ccc; }}</style>"
+    )?;
+    write_text(out, width / 2, MARGIN + LABEL_HEIGHT / 2, &reg.name, "name")?;
+
+    /* Most significant lane goes on top */
+    for (row, (index, lane)) in lanes.iter().enumerate().rev().enumerate() {
+        let lane_hi = index as u32 * LANE_BITS + lane_bits - 1;
+        let top = LABEL_HEIGHT + MARGIN + row as u32 * lane_height;
+
+        for field in lane {
+            let x = MARGIN + (lane_hi - field.to) * CELL_WIDTH;
+            let w = field.width() * CELL_WIDTH;
+            let y = top + LABEL_HEIGHT;
+            let class = if field.is_padding() {
+                "padding"
+            } else {
+                "field"
+            };
+
+            write_text(
+                out,
+                x + CELL_WIDTH / 2,
+                top + LABEL_HEIGHT / 2,
+                &field.to.to_string(),
+                "bits",
+            )?;
+            if field.from != field.to {
+                write_text(
+                    out,
+                    x + w - CELL_WIDTH / 2,
+                    top + LABEL_HEIGHT / 2,
+                    &field.from.to_string(),
+                    "bits",
+                )?;
+            }
+
+            writeln!(
+                out,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" class="{}"/>"#,
+                x, y, w, CELL_HEIGHT, class
+            )?;
+            write_text(
+                out,
+                x + w / 2,
+                y + CELL_HEIGHT / 2,
+                field.name.as_deref().unwrap_or_default(),
+                "field",
+            )?;
+
+            if let Some(value) = reg.value {
+                let y = y + CELL_HEIGHT;
+                writeln!(
+                    out,
+                    r#"  <rect x="{}" y="{}" width="{}" height="{}" class="{}"/>"#,
+                    x, y, w, CELL_HEIGHT, class
+                )?;
+                write_text(
+                    out,
+                    x + w / 2,
+                    y + CELL_HEIGHT / 2,
+                    &field.extract(value).to_string(),
+                    "value",
+                )?;
+            }
+        }
+    }
+
+    writeln!(out, "</svg>")
+}
+
+/// Render register as standalone SVG image
+pub fn to_svg(reg: &RegisterDesc) -> String {
+    let mut out = String::new();
+    write_svg(&mut out, reg).expect("Writing to String can't fail");
+    out
+}
+
+pub fn render(reg: &RegisterDesc, format: Format) -> String {
+    let reg = &reg.resolve();
+    match format {
+        Format::Svg => to_svg(reg),
+        Format::WaveDrom => to_wavedrom(reg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diagram::{to_svg, to_wavedrom},
+        parse_registers,
+    };
+
+    #[test]
+    fn test_split_lanes() {
+        let data = parse_registers("__register 64 { 39:24 X, 0:0 M } REG;");
+        let lanes = data["reg"].split_lanes(32);
+
+        assert_eq!(lanes.len(), 2);
+        let hi: Vec<_> = lanes[1].iter().map(|x| (x.to, x.from)).collect();
+        let lo: Vec<_> = lanes[0].iter().map(|x| (x.to, x.from)).collect();
+        assert_eq!(hi, vec![(63, 40), (39, 32)]);
+        assert_eq!(lo, vec![(31, 24), (23, 1), (0, 0)]);
+        assert_eq!(lanes[0][0].name.as_deref(), Some("X"));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut data = parse_registers("__register 32 { 31:31 N, 3:0 M } CPSR;");
+        let reg = data.get_mut("cpsr").unwrap();
+        reg.value = Some(0x80000003);
+
+        let json: serde_json::Value = serde_json::from_str(&to_wavedrom(reg)).unwrap();
+        assert_eq!(json["config"]["lanes"], 1);
+        assert_eq!(json["reg"][0]["name"], "M");
+        assert_eq!(json["reg"][0]["attr"], "3");
+        assert!(json["reg"][1].get("name").is_none());
+        assert_eq!(json["reg"][2]["bits"], 1);
+
+        let svg = to_svg(reg);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches(r#"class="padding""#).count(), 2);
+    }
+}
@@ -0,0 +1,373 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    fs, io,
+    path::Path,
+};
+
+use crate::{BitfieldDesc, LayoutVariant, RegisterDesc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+        }
+    }
+}
+
+/// Registers are grouped by exception level suffix, e.g. `_EL1` or `_EL12`,
+/// everything else goes to the "Other" group.
+fn group_name(name: &str) -> Option<&str> {
+    let (_, suffix) = name.rsplit_once('_')?;
+    let level = suffix.strip_prefix("EL")?;
+
+    if !level.is_empty() && level.chars().all(|x| x.is_ascii_digit()) {
+        Some(&name[name.len() - suffix.len() - 1..])
+    } else {
+        None
+    }
+}
+
+/// Fields listed in the fields section of a page
+fn is_described(field: &BitfieldDesc) -> bool {
+    field.description.is_some() || field.attr.is_some() || !field.is_padding()
+}
+
+/// Variant heading, e.g. `Data Abort (EC = 0x24, 0x25)`
+fn variant_title(variant: &LayoutVariant) -> String {
+    let values: Vec<_> = variant.values.iter().map(|x| format!("{:#x}", x)).collect();
+    format!(
+        "{} ({} = {})",
+        variant.name,
+        variant.discriminator,
+        values.join(", ")
+    )
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Text safe to put into a Markdown table cell
+fn escape_markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\r', '\n'], "<br>")
+}
+
+fn write_markdown_page(out: &mut String, reg: &RegisterDesc) -> fmt::Result {
+    writeln!(out, "# {}\n", reg.name)?;
+    writeln!(out, "{}-bit register.\n", reg.bits)?;
+    if let Some(encoding) = reg.encoding {
+        writeln!(out, "Encoding: `{}`\n", encoding)?;
+    }
+    if !reg.features.is_empty() {
+        writeln!(out, "Requires {}.\n", reg.features.join(", "))?;
+    }
+
+    if reg.fields.is_empty() {
+        return Ok(());
+    }
+
+    for field in &reg.fields {
+        write!(out, "| {} ", field.bits_label())?;
+    }
+    writeln!(out, "|")?;
+
+    for _ in &reg.fields {
+        write!(out, "|:-:")?;
+    }
+    writeln!(out, "|")?;
+
+    for field in &reg.fields {
+        write!(out, "| {} ", escape_markdown_cell(&field.label()))?;
+    }
+    writeln!(out, "|")?;
+
+    if let Some(value) = reg.value {
+        for field in &reg.fields {
+            write!(out, "| {} ", field.extract(value))?;
+        }
+        writeln!(out, "|")?;
+    }
+
+    let described: Vec<_> = reg.fields.iter().filter(|x| is_described(x)).collect();
+    if !described.is_empty() {
+        writeln!(out, "\n## Fields\n")?;
+        writeln!(out, "| Bits | Name | Description |")?;
+        writeln!(out, "|-|-|-|")?;
+        for field in described {
+            writeln!(
+                out,
+                "| {} | {} | {} |",
+                field.bits_label(),
+                escape_markdown_cell(&field.label()),
+                escape_markdown_cell(field.description.as_deref().unwrap_or_default())
+            )?;
+        }
+    }
+
+    for variant in &reg.variants {
+        writeln!(out, "\n## {}\n", variant_title(variant))?;
+        writeln!(out, "| Bits | Name | Description |")?;
+        writeln!(out, "|-|-|-|")?;
+        for field in variant.fields.iter().filter(|x| is_described(x)) {
+            writeln!(
+                out,
+                "| {} | {} | {} |",
+                field.bits_label(),
+                escape_markdown_cell(&field.label()),
+                escape_markdown_cell(field.description.as_deref().unwrap_or_default())
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_html_header(out: &mut String, title: &str) -> fmt::Result {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", title)?;
+    writeln!(
+        out,
+        "<style>table {{ border-collapse: collapse; }} \
+         td, th {{ border: 1px solid #888; padding: 2px 6px; text-align: center; }}</style>"
+    )?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")
+}
+
+fn write_html_footer(out: &mut String) -> fmt::Result {
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+fn write_html_page(out: &mut String, reg: &RegisterDesc) -> fmt::Result {
+    write_html_header(out, &reg.name)?;
+    writeln!(out, "<p><a href=\"index.html\">Index</a></p>")?;
+    writeln!(out, "<h1>{}</h1>", reg.name)?;
+    writeln!(out, "<p>{}-bit register.</p>", reg.bits)?;
+    if let Some(encoding) = reg.encoding {
+        writeln!(out, "<p>Encoding: <code>{}</code></p>", encoding)?;
+    }
+    if !reg.features.is_empty() {
+        writeln!(out, "<p>Requires {}.</p>", reg.features.join(", "))?;
+    }
+
+    if !reg.fields.is_empty() {
+        writeln!(out, "<table>")?;
+
+        write!(out, "<tr>")?;
+        for field in &reg.fields {
+            write!(out, "<th>{}</th>", field.bits_label())?;
+        }
+        writeln!(out, "</tr>")?;
+
+        write!(out, "<tr>")?;
+        for field in &reg.fields {
+            write!(out, "<td>{}</td>", field.label())?;
+        }
+        writeln!(out, "</tr>")?;
+
+        if let Some(value) = reg.value {
+            write!(out, "<tr>")?;
+            for field in &reg.fields {
+                write!(out, "<td>{}</td>", field.extract(value))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+
+        writeln!(out, "</table>")?;
+    }
+
+    let described: Vec<_> = reg.fields.iter().filter(|x| is_described(x)).collect();
+    if !described.is_empty() {
+        writeln!(out, "<h2>Fields</h2>")?;
+        writeln!(out, "<table>")?;
+        writeln!(
+            out,
+            "<tr><th>Bits</th><th>Name</th><th>Description</th></tr>"
+        )?;
+        for field in described {
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                field.bits_label(),
+                field.label(),
+                field
+                    .description
+                    .as_deref()
+                    .map(escape_html)
+                    .unwrap_or_default()
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    for variant in &reg.variants {
+        writeln!(out, "<h2>{}</h2>", escape_html(&variant_title(variant)))?;
+        writeln!(out, "<table>")?;
+        writeln!(
+            out,
+            "<tr><th>Bits</th><th>Name</th><th>Description</th></tr>"
+        )?;
+        for field in variant.fields.iter().filter(|x| is_described(x)) {
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                field.bits_label(),
+                field.label(),
+                field
+                    .description
+                    .as_deref()
+                    .map(escape_html)
+                    .unwrap_or_default()
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    write_html_footer(out)
+}
+
+fn write_index(
+    out: &mut String,
+    data: &BTreeMap<String, RegisterDesc>,
+    format: Format,
+) -> fmt::Result {
+    let mut groups: BTreeMap<Option<&str>, Vec<&RegisterDesc>> = BTreeMap::new();
+
+    for reg in data.values() {
+        groups.entry(group_name(&reg.name)).or_default().push(reg);
+    }
+
+    /* None sorts first, but "Other" reads better at the end */
+    let other = groups.remove(&None);
+    let groups = groups
+        .into_iter()
+        .map(|(k, v)| (k.unwrap_or_default(), v))
+        .chain(other.map(|v| ("Other", v)));
+
+    let ext = format.extension();
+
+    match format {
+        Format::Markdown => {
+            writeln!(out, "# Registers")?;
+            for (group, regs) in groups {
+                writeln!(out, "\n## {}\n", group)?;
+                for reg in regs {
+                    writeln!(out, "- [{}]({}.{})", reg.name, reg.name, ext)?;
+                }
+            }
+            Ok(())
+        }
+        Format::Html => {
+            write_html_header(out, "Registers")?;
+            writeln!(out, "<h1>Registers</h1>")?;
+            for (group, regs) in groups {
+                writeln!(out, "<h2>{}</h2>", group)?;
+                writeln!(out, "<ul>")?;
+                for reg in regs {
+                    writeln!(
+                        out,
+                        "<li><a href=\"{}.{}\">{}</a></li>",
+                        reg.name, ext, reg.name
+                    )?;
+                }
+                writeln!(out, "</ul>")?;
+            }
+            write_html_footer(out)
+        }
+    }
+}
+
+/// Render a single register reference page
+pub fn register_page(reg: &RegisterDesc, format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Markdown => write_markdown_page(&mut out, reg),
+        Format::Html => write_html_page(&mut out, reg),
+    }
+    .expect("Writing to String can't fail");
+    out
+}
+
+/// Render index page with links to all registers grouped by suffix
+pub fn index_page(data: &BTreeMap<String, RegisterDesc>, format: Format) -> String {
+    let mut out = String::new();
+    write_index(&mut out, data, format).expect("Writing to String can't fail");
+    out
+}
+
+/// Write a page per register and an index page to `dir`
+pub fn write_docs(
+    data: &BTreeMap<String, RegisterDesc>,
+    format: Format,
+    dir: &Path,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    for reg in data.values() {
+        let path = dir.join(format!("{}.{}", reg.name, format.extension()));
+        fs::write(path, register_page(reg, format))?;
+    }
+
+    let path = dir.join(format!("index.{}", format.extension()));
+    fs::write(path, index_page(data, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        doc::{group_name, index_page, register_page, Format},
+        parse_registers,
+    };
+
+    #[test]
+    fn test_group_name() {
+        assert_eq!(group_name("SCTLR_EL1"), Some("_EL1"));
+        assert_eq!(group_name("CNTHP_CTL_EL02"), Some("_EL02"));
+        assert_eq!(group_name("CPSR"), None);
+        assert_eq!(group_name("ID_AA64MMFR0_ELX"), None);
+        assert_eq!(group_name("FOO_EL"), None);
+    }
+
+    #[test]
+    fn test_pages() {
+        let data = parse_registers(
+            "__register 32 { 31:31 N, 3:0 M } CPSR;
+             __register 64 { 0:0 M } SCTLR_EL1;",
+        );
+
+        let page = register_page(&data["cpsr"], Format::Markdown);
+        assert!(page.contains("| 31 | 30..4 | 3..0 |"));
+        assert!(page.contains("| N |  | M |"));
+
+        /* Pipes and line breaks in descriptions don't break the table */
+        let mut reg = data["cpsr"].clone();
+        reg.fields[0].description = Some("Negative | sign\nflag".to_string());
+        let page = register_page(&reg, Format::Markdown);
+        assert!(page.contains("| 31 | N | Negative \\| sign<br>flag |"));
+
+        let page = register_page(&data["cpsr"], Format::Html);
+        assert!(page.contains("<th>30..4</th>"));
+
+        let index = index_page(&data, Format::Markdown);
+        let el1 = index.find("## _EL1").unwrap();
+        let other = index.find("## Other").unwrap();
+        assert!(el1 < other);
+        assert!(index.contains("- [CPSR](CPSR.md)"));
+    }
+}
@@ -0,0 +1,285 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::RegisterDesc;
+
+/// System register encoding used by instructions accessing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Encoding {
+    /// MRS/MSR operands
+    AArch64 {
+        op0: u8,
+        op1: u8,
+        crn: u8,
+        crm: u8,
+        op2: u8,
+    },
+    /// MRC/MCR operands
+    AArch32 {
+        coproc: u8,
+        opc1: u8,
+        crn: u8,
+        crm: u8,
+        opc2: u8,
+    },
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::AArch64 {
+                op0,
+                op1,
+                crn,
+                crm,
+                op2,
+            } => write!(f, "S{}_{}_C{}_C{}_{}", op0, op1, crn, crm, op2),
+            Encoding::AArch32 {
+                coproc,
+                opc1,
+                crn,
+                crm,
+                opc2,
+            } => write!(f, "p{}, {}, c{}, c{}, {}", coproc, opc1, crn, crm, opc2),
+        }
+    }
+}
+
+/// Parse generic `S<op0>_<op1>_C<n>_C<m>_<op2>` name, case-insensitive
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Invalid system register encoding: {}", s);
+        let lower = s.to_lowercase();
+        let parts: Vec<_> = lower
+            .strip_prefix('s')
+            .ok_or_else(err)?
+            .split('_')
+            .collect();
+
+        let [op0, op1, crn, crm, op2] = parts[..] else {
+            return Err(err());
+        };
+        let num = |x: &str, max: u8| match x.parse::<u8>() {
+            Ok(n) if n <= max && !x.starts_with('+') => Ok(n),
+            _ => Err(err()),
+        };
+
+        Ok(Encoding::AArch64 {
+            op0: num(op0, 3)?,
+            op1: num(op1, 7)?,
+            crn: num(crn.strip_prefix('c').ok_or_else(err)?, 15)?,
+            crm: num(crm.strip_prefix('c').ok_or_else(err)?, 15)?,
+            op2: num(op2, 7)?,
+        })
+    }
+}
+
+/// System register access decoded from an instruction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Access {
+    pub encoding: Encoding,
+    /// MSR/MCR write the register, MRS/MRC read it
+    pub write: bool,
+    /// General purpose register transferring the value
+    pub rt: u8,
+}
+
+fn bits(insn: u32, hi: u32, lo: u32) -> u8 {
+    ((insn >> lo) & ((1 << (hi - lo + 1)) - 1)) as u8
+}
+
+/// Decode MRS/MSR (register) A64 or MRC/MCR A32/T32 instruction
+pub fn decode_insn(insn: u32) -> Option<Access> {
+    /* MRS/MSR: 1101 0101 00 L 1 o0 op1 CRn CRm op2 Rt */
+    if insn & 0xffd0_0000 == 0xd510_0000 {
+        return Some(Access {
+            encoding: Encoding::AArch64 {
+                op0: 2 + bits(insn, 19, 19),
+                op1: bits(insn, 18, 16),
+                crn: bits(insn, 15, 12),
+                crm: bits(insn, 11, 8),
+                op2: bits(insn, 7, 5),
+            },
+            write: bits(insn, 21, 21) == 0,
+            rt: bits(insn, 4, 0),
+        });
+    }
+
+    /*
+     * MRC/MCR: cond 1110 opc1 L CRn Rt coproc opc2 1 CRm, T32 encoding
+     * has the same layout with 1110 in place of cond
+     */
+    let coproc = bits(insn, 11, 8);
+    if insn & 0x0f00_0010 == 0x0e00_0010 && (coproc == 14 || coproc == 15) {
+        return Some(Access {
+            encoding: Encoding::AArch32 {
+                coproc,
+                opc1: bits(insn, 23, 21),
+                crn: bits(insn, 19, 16),
+                crm: bits(insn, 3, 0),
+                opc2: bits(insn, 7, 5),
+            },
+            write: bits(insn, 20, 20) == 0,
+            rt: bits(insn, 15, 12),
+        });
+    }
+
+    None
+}
+
+/// Find register by encoding, IMP DEF registers may also be defined under
+/// their generic name without explicit encoding
+pub fn find_by_encoding(
+    data: &BTreeMap<String, RegisterDesc>,
+    encoding: Encoding,
+) -> Option<&RegisterDesc> {
+    data.values()
+        .find(|x| x.encoding == Some(encoding))
+        .or_else(|| data.get(&encoding.to_string().to_lowercase()))
+}
+
+/// Find register by name or by its generic `S<op0>_<op1>_C<n>_C<m>_<op2>` name
+pub fn find_register<'a>(
+    data: &'a BTreeMap<String, RegisterDesc>,
+    name: &str,
+) -> Option<&'a RegisterDesc> {
+    data.get(&name.to_lowercase()).or_else(|| {
+        name.parse()
+            .ok()
+            .and_then(|encoding| find_by_encoding(data, encoding))
+    })
+}
+
+impl Access {
+    /// Disassemble access, using register name from `data` when known
+    pub fn disassemble(&self, data: &BTreeMap<String, RegisterDesc>) -> String {
+        let name = find_by_encoding(data, self.encoding).map(|x| x.name.clone());
+
+        match (self.encoding, self.write) {
+            (Encoding::AArch64 { .. }, write) => {
+                let rt = match self.rt {
+                    31 => String::from("XZR"),
+                    x => format!("X{}", x),
+                };
+                let name = name.unwrap_or_else(|| self.encoding.to_string());
+                if write {
+                    format!("MSR {}, {}", name, rt)
+                } else {
+                    format!("MRS {}, {}", rt, name)
+                }
+            }
+            (
+                Encoding::AArch32 {
+                    coproc,
+                    opc1,
+                    crn,
+                    crm,
+                    opc2,
+                },
+                write,
+            ) => {
+                let insn = if write { "MCR" } else { "MRC" };
+                let rt = match self.rt {
+                    15 => String::from("APSR_nzcv"),
+                    x => format!("R{}", x),
+                };
+                let mut s = format!(
+                    "{} p{}, {}, {}, c{}, c{}, {}",
+                    insn, coproc, opc1, rt, crn, crm, opc2
+                );
+                if let Some(name) = name {
+                    s.push_str(&format!(" ; {}", name));
+                }
+                s
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        encoding::{decode_insn, find_register, Encoding},
+        meta::apply_meta,
+        parse_registers,
+    };
+
+    #[test]
+    fn test_decode() {
+        let mut data = parse_registers(
+            "__register 64 { 0:0 M } SCTLR_EL1;
+             __register 32 { 0:0 M } SCTLR;",
+        );
+        let meta = "__encoding SCTLR_EL1 (3, 0, 1, 0, 0);
+                    __encoding SCTLR p15 (0, 1, 0, 0);";
+        apply_meta(&mut data, meta).unwrap();
+
+        let access = decode_insn(0xd5381000).unwrap();
+        assert!(!access.write);
+        assert_eq!(access.rt, 0);
+        assert_eq!(access.encoding.to_string(), "S3_0_C1_C0_0");
+        assert_eq!(access.disassemble(&data), "MRS X0, SCTLR_EL1");
+
+        /* MSR SCTLR_EL1, X1 */
+        let access = decode_insn(0xd5181001).unwrap();
+        assert_eq!(access.disassemble(&data), "MSR SCTLR_EL1, X1");
+
+        /* MRS X30, S3_0_C15_C2_0 */
+        let access = decode_insn(0xd538f21e).unwrap();
+        assert_eq!(access.disassemble(&data), "MRS X30, S3_0_C15_C2_0");
+
+        /* MRC p15, 0, R0, c1, c0, 0 */
+        let access = decode_insn(0xee110f10).unwrap();
+        assert_eq!(
+            access.encoding,
+            Encoding::AArch32 {
+                coproc: 15,
+                opc1: 0,
+                crn: 1,
+                crm: 0,
+                opc2: 0
+            }
+        );
+        assert_eq!(
+            access.disassemble(&data),
+            "MRC p15, 0, R0, c1, c0, 0 ; SCTLR"
+        );
+
+        /* MCR p15, 0, R2, c1, c0, 0 */
+        assert!(decode_insn(0xee012f10).unwrap().write);
+
+        /* NOP */
+        assert_eq!(decode_insn(0xd503201f), None);
+    }
+
+    #[test]
+    fn test_generic_name() {
+        let mut data = parse_registers(
+            "__register 64 { 0:0 M } SCTLR_EL1;
+             __register 64 { 1:0 X } S3_1_C15_C2_0;",
+        );
+        apply_meta(&mut data, "__encoding SCTLR_EL1 (3, 0, 1, 0, 0);").unwrap();
+
+        let encoding: Encoding = "s3_0_c1_c0_0".parse().unwrap();
+        assert_eq!(encoding.to_string(), "S3_0_C1_C0_0");
+        assert!("S3_0_C16_C0_0".parse::<Encoding>().is_err());
+        assert!("S3_0_C1_C0".parse::<Encoding>().is_err());
+        assert!("SCTLR_EL1".parse::<Encoding>().is_err());
+
+        assert_eq!(
+            find_register(&data, "S3_0_C1_C0_0").unwrap().name,
+            "SCTLR_EL1"
+        );
+        assert_eq!(find_register(&data, "sctlr_el1").unwrap().name, "SCTLR_EL1");
+        assert_eq!(
+            find_register(&data, "S3_1_C15_C2_0").unwrap().name,
+            "S3_1_C15_C2_0"
+        );
+        assert!(find_register(&data, "S3_0_C15_C2_0").is_none());
+
+        /* MRS X0, S3_1_C15_C2_0 */
+        let access = decode_insn(0xd539f200).unwrap();
+        assert_eq!(access.disassemble(&data), "MRS X0, S3_1_C15_C2_0");
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    encoding::{find_register, Access, Encoding},
+    render::{render, RenderOptions},
+    RegisterDesc,
+};
+
+/// Exception syndrome decoded with the ISS layout selected by EC
+#[derive(Debug, Clone)]
+pub struct Syndrome {
+    /// Syndrome register holding the decoded value
+    pub reg: RegisterDesc,
+    pub ec: u64,
+    /// Exception class meaning, e.g. `Data Abort from a lower Exception level`
+    pub class: Option<String>,
+    /// One line summary of the ISS, e.g. trapped instruction for EC 0x18
+    pub summary: Option<String>,
+}
+
+fn field(reg: &RegisterDesc, name: &str) -> Option<u64> {
+    let value = reg.value?;
+    reg.fields
+        .iter()
+        .find(|x| x.name.as_deref() == Some(name))
+        .map(|x| x.extract(value))
+}
+
+/// Meaning of the field value, or its raw value when there is no named one
+fn meaning(reg: &RegisterDesc, name: &str) -> Option<String> {
+    let value = reg.value?;
+    let field = reg
+        .fields
+        .iter()
+        .find(|x| x.name.as_deref() == Some(name))?;
+    let x = field.extract(value);
+
+    Some(match field.meaning(x) {
+        Some(m) => m.to_string(),
+        None => format!("{} {:#x}", name, x),
+    })
+}
+
+fn abort_summary(reg: &RegisterDesc, status: &str) -> Option<String> {
+    let mut s = meaning(reg, status)?;
+
+    if let Some(wnr) = field(reg, "WnR") {
+        let access = if wnr == 1 { "Write" } else { "Read" };
+        s = format!("{} access, {}", access, s);
+    }
+    if let Some(aet) = meaning(reg, "AET") {
+        s = format!("{}, {}", aet, s);
+    }
+    if field(reg, "ISV") == Some(1) {
+        let rt = field(reg, "SRT")?;
+        let prefix = if field(reg, "SF") == Some(1) {
+            "X"
+        } else {
+            "W"
+        };
+        s.push_str(&format!(
+            ", {} transfer to {}{}",
+            meaning(reg, "SAS")?.to_lowercase(),
+            prefix,
+            rt
+        ));
+    }
+    if field(reg, "FnV") == Some(1) {
+        s.push_str(", FAR is not valid");
+    }
+    Some(s)
+}
+
+fn trap_summary(data: &BTreeMap<String, RegisterDesc>, reg: &RegisterDesc) -> Option<String> {
+    let operand = |name| field(reg, name).map(|x| x as u8);
+    let access = Access {
+        encoding: Encoding::AArch64 {
+            op0: operand("Op0")?,
+            op1: operand("Op1")?,
+            crn: operand("CRn")?,
+            crm: operand("CRm")?,
+            op2: operand("Op2")?,
+        },
+        write: field(reg, "Direction")? == 0,
+        rt: operand("Rt")?,
+    };
+    Some(access.disassemble(data))
+}
+
+/// Decode `value` of syndrome register `register`, e.g. `ESR_EL1`. Returns
+/// `None` if the register or its EC field is missing from `data`.
+pub fn decode_esr(
+    data: &BTreeMap<String, RegisterDesc>,
+    register: &str,
+    value: u64,
+) -> Option<Syndrome> {
+    let reg = RegisterDesc {
+        value: Some(value),
+        ..find_register(data, register)?.clone()
+    }
+    .resolve();
+
+    let ec = field(&reg, "EC")?;
+    let summary = match ec {
+        0x24 | 0x25 | 0x2f if field(&reg, "IDS") != Some(1) => abort_summary(&reg, "DFSC"),
+        0x2f => Some(String::from("IMPLEMENTATION DEFINED syndrome")),
+        0x20 | 0x21 => abort_summary(&reg, "IFSC"),
+        0x15..=0x17 => {
+            let insn = ["SVC", "HVC", "SMC"][ec as usize - 0x15];
+            field(&reg, "imm16").map(|x| format!("{} #{:#x}", insn, x))
+        }
+        0x18 => trap_summary(data, &reg),
+        _ => None,
+    };
+
+    let class = reg
+        .fields
+        .iter()
+        .find(|x| x.name.as_deref() == Some("EC"))
+        .and_then(|x| x.meaning(ec))
+        .map(String::from);
+
+    Some(Syndrome {
+        reg,
+        class,
+        ec,
+        summary,
+    })
+}
+
+impl Syndrome {
+    /// Exception class and summary lines followed by the decoded register
+    pub fn render(&self, opts: &RenderOptions) -> String {
+        let mut out = format!(
+            "EC {:#04x}: {}\n",
+            self.ec,
+            self.class.as_deref().unwrap_or("Unknown exception class")
+        );
+        if let Some(summary) = &self.summary {
+            out.push_str(summary);
+            out.push('\n');
+        }
+        out.push_str(&render(&self.reg, opts));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        esr::decode_esr,
+        meta::{apply_meta, BUILTIN_META},
+        parse_registers,
+    };
+
+    #[test]
+    fn test_decode_esr() {
+        let mut data = parse_registers(
+            "__register 64 { 31:26 EC, 25:25 IL, 24:0 ISS } ESR_EL1;
+             __register 64 { 31:26 EC, 25:25 IL, 24:0 ISS } ESR_EL3;
+             __register 64 { 0:0 M } SCTLR_EL1;",
+        );
+        apply_meta(&mut data, BUILTIN_META).unwrap();
+
+        let esr = decode_esr(&data, "ESR_EL1", 0x96000045).unwrap();
+        assert_eq!(esr.ec, 0x25);
+        assert_eq!(
+            esr.class.as_deref(),
+            Some("Data Abort without a change in Exception level")
+        );
+        assert_eq!(
+            esr.summary.as_deref(),
+            Some("Write access, Translation fault, level 1")
+        );
+
+        /* LDR W1, [X0] with ISV set */
+        let esr = decode_esr(&data, "esr_el1", 0x93810007).unwrap();
+        assert_eq!(
+            esr.summary.as_deref(),
+            Some("Read access, Translation fault, level 3, word transfer to W1")
+        );
+
+        let esr = decode_esr(&data, "ESR_EL1", 0x5600002a).unwrap();
+        assert_eq!(esr.summary.as_deref(), Some("SVC #0x2a"));
+
+        /* MRS X2, SCTLR_EL1 */
+        let esr = decode_esr(&data, "ESR_EL1", 0x62300441).unwrap();
+        assert_eq!(esr.summary.as_deref(), Some("MRS X2, SCTLR_EL1"));
+
+        let esr = decode_esr(&data, "ESR_EL1", 0xbe000000).unwrap();
+        assert_eq!(esr.ec, 0x2f);
+        assert_eq!(
+            esr.summary.as_deref(),
+            Some("Uncontainable, Uncategorized error")
+        );
+
+        assert!(esr
+            .render(&Default::default())
+            .starts_with("EC 0x2f: SError interrupt\n"));
+
+        /* ESR_ELx metadata applies to every exception level */
+        let esr = decode_esr(&data, "ESR_EL3", 0x96000045).unwrap();
+        assert_eq!(
+            esr.summary.as_deref(),
+            Some("Write access, Translation fault, level 1")
+        );
+        assert!(decode_esr(&data, "ESR_EL2", 0).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use std::{num::IntErrorKind, str::FromStr};
+
+use crate::{expr_parser, RegisterDesc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Or,
+    Xor,
+    And,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+}
+
+/// Integer expression over the current register value, e.g. `$ | (1 << 12)`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(u64),
+    /// `$`, the current value
+    Current,
+    /// `$.M`, field of the current value
+    Field(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+/// Number literal with optional `_` separators, used by the grammar
+pub fn parse_num(s: &str, radix: u32) -> Result<u64, &'static str> {
+    let digits = s.replace('_', "");
+    if digits.is_empty() {
+        return Err("Number has no digits");
+    }
+    u64::from_str_radix(&digits, radix).map_err(|e| match e.kind() {
+        IntErrorKind::PosOverflow => "Number doesn't fit into 64 bits",
+        _ => "Number has invalid digits",
+    })
+}
+
+/// Number with `0x`, `0o` or `0b` prefix or, without a prefix, in `radix`.
+/// `_` separates digits.
+pub fn parse_number(s: &str, radix: u32) -> Result<u64, String> {
+    let lower = s.to_lowercase();
+    let result = match lower.get(..2) {
+        Some("0x") => parse_num(&lower[2..], 16),
+        Some("0o") => parse_num(&lower[2..], 8),
+        Some("0b") => parse_num(&lower[2..], 2),
+        _ => parse_num(&lower, radix),
+    };
+    result.map_err(|e| format!("Invalid number {}: {}", s, e))
+}
+
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expr_parser::ExprParser::new()
+            .parse(s)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Expr {
+    /// Whether the expression refers to the current value
+    pub fn uses_current(&self) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Current | Expr::Field(_) => true,
+            Expr::Not(e) | Expr::Neg(e) => e.uses_current(),
+            Expr::Binary(l, _, r) => l.uses_current() || r.uses_current(),
+        }
+    }
+
+    /*
+     * Arithmetic wraps around and shifts by 64 or more bits give 0. `$` is 0
+     * when the register has no value yet, like for field assignments.
+     */
+    pub fn eval(&self, reg: Option<&RegisterDesc>) -> Result<u64, String> {
+        let current = || -> Result<(&RegisterDesc, u64), String> {
+            let reg = reg.ok_or("No register selected")?;
+            Ok((reg, reg.value.unwrap_or(0)))
+        };
+
+        Ok(match self {
+            Expr::Num(x) => *x,
+            Expr::Current => current()?.1,
+            Expr::Field(name) => {
+                let (reg, value) = current()?;
+                let field = reg
+                    .field(name)
+                    .ok_or_else(|| format!("Unknown field: {}", name))?;
+                field.extract(value)
+            }
+            Expr::Not(e) => !e.eval(reg)?,
+            Expr::Neg(e) => e.eval(reg)?.wrapping_neg(),
+            Expr::Binary(l, op, r) => {
+                let (l, r) = (l.eval(reg)?, r.eval(reg)?);
+                let shift = u32::try_from(r).unwrap_or(u32::MAX);
+                match op {
+                    Op::Or => l | r,
+                    Op::Xor => l ^ r,
+                    Op::And => l & r,
+                    Op::Shl => l.checked_shl(shift).unwrap_or(0),
+                    Op::Shr => l.checked_shr(shift).unwrap_or(0),
+                    Op::Add => l.wrapping_add(r),
+                    Op::Sub => l.wrapping_sub(r),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        expr::{parse_number, Expr},
+        parse_registers,
+    };
+
+    fn eval(s: &str) -> Result<u64, String> {
+        s.parse::<Expr>()?.eval(None)
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(eval("0x1_000"), Ok(0x1000));
+        assert_eq!(eval("0b1010 | 0o7"), Ok(0xf));
+        assert_eq!(eval("1 << 12 | 1 << 2"), Ok(0x1004));
+        assert_eq!(eval("1 + 2 << 3"), Ok(24));
+        assert_eq!(eval("0xff & ~0xf ^ 0x1"), Ok(0xf1));
+        assert_eq!(eval("-1"), Ok(u64::MAX));
+        assert_eq!(eval("1 << 64"), Ok(0));
+        assert!(eval("0x1_0000_0000_0000_0000").is_err());
+        assert!(eval("1 +").is_err());
+        assert!(eval("$").is_err());
+        assert!("sctlr_el1".parse::<Expr>().is_err());
+
+        let mut data = parse_registers("__register 64 { 12:12 I, 3:0 M } SCTLR_EL1;");
+        let reg = data.get_mut("sctlr_el1").unwrap();
+        reg.value = Some(0x5);
+
+        let expr: Expr = "$ | (1 << 12)".parse().unwrap();
+        assert!(expr.uses_current());
+        assert_eq!(expr.eval(Some(reg)), Ok(0x1005));
+        let expr: Expr = "$.m + $.I".parse().unwrap();
+        assert_eq!(expr.eval(Some(reg)), Ok(5));
+        let expr: Expr = "$.X".parse().unwrap();
+        assert!(expr.eval(Some(reg)).is_err());
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("0B101", 10), Ok(5));
+        assert_eq!(parse_number("0o1_7", 10), Ok(15));
+        assert_eq!(parse_number("0x30D0_0805", 10), Ok(0x30d00805));
+        assert_eq!(parse_number("10000000", 10), Ok(10000000));
+        assert_eq!(parse_number("10000000", 16), Ok(0x10000000));
+        assert_eq!(
+            parse_number("12ab", 10),
+            Err(String::from(
+                "Invalid number 12ab: Number has invalid digits"
+            ))
+        );
+        assert!(parse_number("0x", 16).is_err());
+    }
+}
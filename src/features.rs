@@ -0,0 +1,192 @@
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/*
+ * Features mandatory from each Armv8 extension on top of the previous one.
+ * Only the features referenced by register metadata matter here, so the
+ * lists are not exhaustive. Armv9.N includes Armv8.(N+5) and SVE2.
+ */
+const ARMV8_FEATURES: [&[&str]; 8] = [
+    &[],
+    &[
+        "FEAT_PAN",
+        "FEAT_LSE",
+        "FEAT_VHE",
+        "FEAT_LOR",
+        "FEAT_RDM",
+        "FEAT_HPDS",
+        "FEAT_VMID16",
+        "FEAT_CRC32",
+    ],
+    &[
+        "FEAT_PAN2",
+        "FEAT_UAO",
+        "FEAT_DPB",
+        "FEAT_TTCNP",
+        "FEAT_XNX",
+        "FEAT_LSMAOC",
+        "FEAT_HPDS2",
+        "FEAT_RAS",
+        "FEAT_IESB",
+    ],
+    &["FEAT_PAuth", "FEAT_LRCPC", "FEAT_JSCVT", "FEAT_FCMA"],
+    &[
+        "FEAT_DIT",
+        "FEAT_TTL",
+        "FEAT_S2FWB",
+        "FEAT_LSE2",
+        "FEAT_FlagM",
+        "FEAT_LRCPC2",
+        "FEAT_TLBIOS",
+        "FEAT_TLBIRANGE",
+        "FEAT_IDST",
+    ],
+    /* SB, SSBS, SPECRES and CSV2 are OPTIONAL from Armv8.0, mandatory from Armv8.5 */
+    &[
+        "FEAT_SB",
+        "FEAT_SSBS",
+        "FEAT_SPECRES",
+        "FEAT_BTI",
+        "FEAT_FlagM2",
+        "FEAT_FRINTTS",
+        "FEAT_DPB2",
+        "FEAT_CSV2",
+        "FEAT_E0PD",
+    ],
+    &["FEAT_BF16", "FEAT_I8MM", "FEAT_ECV", "FEAT_FGT"],
+    &["FEAT_HCX", "FEAT_XS", "FEAT_PAN3"],
+];
+
+const ARMV9_FEATURES: &[&str] = &["FEAT_SVE", "FEAT_SVE2"];
+
+/// Implemented architecture features, e.g. parsed from `armv8.2 + SVE`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureSet(BTreeSet<String>);
+
+/// Normalize `sve` or `feat_sve` to `FEAT_SVE`
+fn feature_name(s: &str) -> String {
+    let s = s.to_uppercase();
+    if s.starts_with("FEAT_") {
+        s
+    } else {
+        format!("FEAT_{}", s)
+    }
+}
+
+/// Parse `armv8`, `v8.2` or `armv9.1` to the number of Armv8 extensions
+/// and whether it's Armv9. `None` if `s` is not a version, an error if it's
+/// a version this crate doesn't know.
+fn parse_version(s: &str) -> Result<Option<(usize, bool)>, String> {
+    let lower = s.to_lowercase();
+    let Some(version) = lower
+        .strip_prefix("arm")
+        .unwrap_or(&lower)
+        .strip_prefix('v')
+    else {
+        return Ok(None);
+    };
+    let (major, minor) = version.split_once('.').unwrap_or((version, "0"));
+    let is_number = |x: &str| !x.is_empty() && x.bytes().all(|c| c.is_ascii_digit());
+    if !is_number(major) || !is_number(minor) {
+        return Ok(None);
+    }
+
+    let minor = minor.parse::<usize>().unwrap_or(usize::MAX);
+    match major {
+        "8" if minor < ARMV8_FEATURES.len() => Ok(Some((minor, false))),
+        "9" if minor < ARMV8_FEATURES.len() - 5 => Ok(Some((minor + 5, true))),
+        _ => Err(format!("Unknown architecture version: {}", s)),
+    }
+}
+
+impl FeatureSet {
+    pub fn contains(&self, feature: &str) -> bool {
+        self.0.contains(&feature.to_uppercase())
+    }
+
+    /// Whether all of `required` features are implemented
+    pub fn has_all(&self, required: &[String]) -> bool {
+        required.iter().all(|x| self.contains(x))
+    }
+
+    /// Features of `required` which are not implemented
+    pub fn missing<'a>(&'a self, required: &'a [String]) -> impl Iterator<Item = &'a String> {
+        required.iter().filter(|x| !self.contains(x))
+    }
+}
+
+/// Profile is a list of architecture versions and features separated by `+`
+/// or `,`, e.g. `armv8.2 + SVE + FEAT_MTE`
+impl FromStr for FeatureSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = BTreeSet::new();
+
+        for item in s.split(['+', ',']).map(str::trim) {
+            if item.is_empty() {
+                return Err(format!("Empty item in feature profile: {}", s));
+            }
+
+            match parse_version(item)? {
+                Some((minor, armv9)) => {
+                    let features = ARMV8_FEATURES[..=minor].iter().flat_map(|x| x.iter());
+                    set.extend(features.map(|x| x.to_uppercase()));
+                    if armv9 {
+                        set.extend(ARMV9_FEATURES.iter().map(|x| x.to_uppercase()));
+                    }
+                }
+                None => {
+                    set.insert(feature_name(item));
+                }
+            }
+        }
+        Ok(FeatureSet(set))
+    }
+}
+
+impl fmt::Display for FeatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let features: Vec<_> = self.0.iter().map(String::as_str).collect();
+        write!(f, "{}", features.join(" + "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::features::FeatureSet;
+
+    #[test]
+    fn test_profile() {
+        let profile: FeatureSet = "armv8.2 + SVE".parse().unwrap();
+        assert!(profile.contains("FEAT_PAN"));
+        assert!(profile.contains("FEAT_UAO"));
+        assert!(profile.contains("FEAT_SVE"));
+        assert!(!profile.contains("FEAT_PAuth"));
+        assert!(!profile.contains("FEAT_SSBS"));
+
+        let profile: FeatureSet = "v9.0, feat_mte".parse().unwrap();
+        assert!(profile.contains("FEAT_PAuth"));
+        assert!(profile.contains("FEAT_SSBS"));
+        assert!(profile.contains("FEAT_SVE2"));
+        assert!(profile.contains("FEAT_MTE"));
+        assert!(!profile.contains("FEAT_BF16"));
+
+        let required = vec![String::from("FEAT_MTE"), String::from("FEAT_BTI")];
+        assert!(profile.has_all(&required));
+        let profile: FeatureSet = "armv8".parse().unwrap();
+        assert_eq!(profile.missing(&required).count(), 2);
+
+        assert!("armv8.2 +".parse::<FeatureSet>().is_err());
+        /* Versions newer than the feature tables are not taken for features */
+        assert_eq!(
+            "armv8.8".parse::<FeatureSet>(),
+            Err(String::from("Unknown architecture version: armv8.8"))
+        );
+        assert!("armv9.3 + SVE".parse::<FeatureSet>().is_err());
+        assert!("v10".parse::<FeatureSet>().is_err());
+        assert!("armv9.2"
+            .parse::<FeatureSet>()
+            .unwrap()
+            .contains("FEAT_PAN3"));
+    }
+}
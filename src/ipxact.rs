@@ -0,0 +1,177 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+};
+
+use crate::RegisterDesc;
+
+const NAMESPACE: &str = "http://www.accellera.org/XMLSchema/IPXACT/1685-2014";
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/*
+ * System registers are not memory mapped, so addresses are synthetic: every
+ * register gets the next offset aligned to its own size, in name order.
+ */
+fn register_bytes(reg: &RegisterDesc) -> u64 {
+    (u64::from(reg.bits) / 8).max(4).next_power_of_two()
+}
+
+fn write_register(out: &mut String, reg: &RegisterDesc, offset: u64) -> std::fmt::Result {
+    writeln!(out, "          <ipxact:register>")?;
+    writeln!(out, "            <ipxact:name>{}</ipxact:name>", reg.name)?;
+    writeln!(
+        out,
+        "            <ipxact:addressOffset>0x{:x}</ipxact:addressOffset>",
+        offset
+    )?;
+    writeln!(out, "            <ipxact:size>{}</ipxact:size>", reg.bits)?;
+
+    /* IP-XACT requires unique field names, split fields get a bit suffix */
+    let mut seen = HashSet::new();
+
+    for field in reg.fields.iter().rev() {
+        if field.is_padding() {
+            continue;
+        }
+
+        let name = field.name.as_deref().unwrap_or_default();
+        let name = if seen.insert(name) {
+            name.to_string()
+        } else {
+            format!("{}_{}", name, field.from)
+        };
+
+        writeln!(out, "            <ipxact:field>")?;
+        writeln!(out, "              <ipxact:name>{}</ipxact:name>", name)?;
+        if let Some(description) = &field.description {
+            writeln!(
+                out,
+                "              <ipxact:description>{}</ipxact:description>",
+                escape_xml(description)
+            )?;
+        }
+        writeln!(
+            out,
+            "              <ipxact:bitOffset>{}</ipxact:bitOffset>",
+            field.from
+        )?;
+        writeln!(
+            out,
+            "              <ipxact:bitWidth>{}</ipxact:bitWidth>",
+            field.width()
+        )?;
+        writeln!(out, "            </ipxact:field>")?;
+    }
+
+    /* A register needs at least one field, one without named fields is reserved */
+    if seen.is_empty() {
+        writeln!(out, "            <ipxact:field>")?;
+        writeln!(out, "              <ipxact:name>RESERVED</ipxact:name>")?;
+        writeln!(out, "              <ipxact:bitOffset>0</ipxact:bitOffset>")?;
+        writeln!(
+            out,
+            "              <ipxact:bitWidth>{}</ipxact:bitWidth>",
+            reg.bits
+        )?;
+        writeln!(out, "            </ipxact:field>")?;
+    }
+
+    writeln!(out, "          </ipxact:register>")
+}
+
+fn write_component(out: &mut String, data: &BTreeMap<String, RegisterDesc>) -> std::fmt::Result {
+    let mut offset: u64 = 0;
+    let mut registers = String::new();
+    let mut width = 32;
+
+    for reg in data.values() {
+        let size = register_bytes(reg);
+        offset = offset.div_ceil(size) * size;
+        write_register(&mut registers, reg, offset)?;
+        offset += size;
+        width = width.max(reg.bits);
+    }
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<ipxact:component xmlns:ipxact="{}">"#, NAMESPACE)?;
+    writeln!(out, "  <ipxact:vendor>arm.com</ipxact:vendor>")?;
+    writeln!(out, "  <ipxact:library>armv8</ipxact:library>")?;
+    writeln!(out, "  <ipxact:name>sysregs</ipxact:name>")?;
+    writeln!(out, "  <ipxact:version>1.0</ipxact:version>")?;
+    writeln!(out, "  <ipxact:memoryMaps>")?;
+    writeln!(out, "    <ipxact:memoryMap>")?;
+    writeln!(out, "      <ipxact:name>sysregs</ipxact:name>")?;
+    writeln!(out, "      <ipxact:addressBlock>")?;
+    writeln!(out, "        <ipxact:name>sysregs</ipxact:name>")?;
+    writeln!(out, "        <ipxact:baseAddress>0x0</ipxact:baseAddress>")?;
+    writeln!(
+        out,
+        "        <ipxact:range>0x{:x}</ipxact:range>",
+        offset.max(1)
+    )?;
+    writeln!(out, "        <ipxact:width>{}</ipxact:width>", width)?;
+    writeln!(out, "        <ipxact:registerFile>")?;
+    writeln!(out, "          <ipxact:name>sysregs</ipxact:name>")?;
+    writeln!(
+        out,
+        "          <ipxact:addressOffset>0x0</ipxact:addressOffset>"
+    )?;
+    writeln!(
+        out,
+        "          <ipxact:range>0x{:x}</ipxact:range>",
+        offset.max(1)
+    )?;
+    write!(out, "{}", registers)?;
+    writeln!(out, "        </ipxact:registerFile>")?;
+    writeln!(out, "      </ipxact:addressBlock>")?;
+    writeln!(out, "    </ipxact:memoryMap>")?;
+    writeln!(out, "  </ipxact:memoryMaps>")?;
+    writeln!(out, "</ipxact:component>")
+}
+
+/// Render the register database as an IP-XACT (IEEE 1685-2014) component
+/// with a single memory map holding every register in one register file.
+pub fn to_ipxact(data: &BTreeMap<String, RegisterDesc>) -> String {
+    let mut out = String::new();
+    write_component(&mut out, data).expect("Writing to String can't fail");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{ipxact::to_ipxact, parse_registers};
+
+    #[test]
+    fn test_ipxact() {
+        let data = parse_registers(
+            "__register 32 { 31:31 N, 7:4 A, 1:1 A } FIRST;
+             __register 64 { 63:0 X } SECOND;",
+        );
+        let xml = to_ipxact(&data);
+
+        assert!(xml.contains("<ipxact:name>FIRST</ipxact:name>"));
+        assert!(xml.contains("<ipxact:name>A_4</ipxact:name>"));
+        assert!(xml.contains("<ipxact:bitOffset>4</ipxact:bitOffset>"));
+        assert!(xml.contains("<ipxact:bitWidth>64</ipxact:bitWidth>"));
+        assert!(xml.contains("<ipxact:addressOffset>0x8</ipxact:addressOffset>"));
+        /* Padding is not exported */
+        assert_eq!(xml.matches("<ipxact:field>").count(), 4);
+
+        /* Register with padding only still has a field */
+        let data = parse_registers("__register 32 { 31:0 RES0 } RESERVED_REG;");
+        let xml = to_ipxact(&data);
+        assert!(xml.contains("<ipxact:name>RESERVED_REG</ipxact:name>"));
+        assert_eq!(xml.matches("<ipxact:field>").count(), 1);
+        assert!(xml.contains("<ipxact:bitWidth>32</ipxact:bitWidth>"));
+
+        let xml = to_ipxact(&BTreeMap::new());
+        assert!(xml.contains("<ipxact:registerFile>"));
+    }
+}
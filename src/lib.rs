@@ -1,6 +1,6 @@
 use crate::ast::Statement;
 use core::fmt;
-use std::{cmp::max, collections::BTreeMap};
+use std::collections::BTreeMap;
 
 #[cfg(test)]
 use crate::ast::{Bitfield, Range, Register};
@@ -9,7 +9,29 @@ use crate::ast::{Bitfield, Range, Register};
 extern crate lalrpop_util;
 
 lalrpop_mod!(#[allow(clippy::all)] pub registers); // syntesized by LALRPOP
+lalrpop_mod!(
+    #[allow(clippy::all)]
+    meta_parser,
+    "/meta.rs"
+); // syntesized by LALRPOP
+lalrpop_mod!(
+    #[allow(clippy::all)]
+    expr_parser,
+    "/expr.rs"
+); // syntesized by LALRPOP
 mod ast;
+pub mod batch;
+pub mod diagram;
+pub mod doc;
+pub mod encoding;
+pub mod esr;
+pub mod expr;
+pub mod features;
+pub mod ipxact;
+pub mod meta;
+pub mod render;
+pub mod report;
+pub mod search;
 
 #[cfg(test)]
 fn check_register(input: &str, reference: Register) {
@@ -173,21 +195,160 @@ fn program() {
     assert_eq!(prog.len(), 2);
     assert!(matches!(prog[0], Statement::Comment));
     assert!(matches!(&prog[1], Statement::Register(_)));
+
+    assert!(try_parse_registers("__register 32 {} REG").is_err());
+    let (data, skipped) =
+        try_parse_registers("__register 32 { 40:0 X } BAD; __register 32 {} REG;").unwrap();
+    assert_eq!((data.len(), skipped), (1, 1));
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldValueDesc {
+    pub value: u64,
+    pub meaning: String,
+}
+
+/// Architectural behaviour of reserved or special bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldAttr {
+    Res0,
+    Res1,
+    RazWi,
+    Unknown,
+    Unpredictable,
+}
+
+impl fmt::Display for FieldAttr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FieldAttr::Res0 => "RES0",
+            FieldAttr::Res1 => "RES1",
+            FieldAttr::RazWi => "RAZ/WI",
+            FieldAttr::Unknown => "UNKNOWN",
+            FieldAttr::Unpredictable => "UNPREDICTABLE",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct BitfieldDesc {
     pub from: u32,
     pub to: u32,
     pub name: Option<String>,
+    /// Named values, e.g. `0b0101` is `EL1h` for `SPSR_EL1.M`
+    pub values: Vec<FieldValueDesc>,
+    pub description: Option<String>,
+    pub attr: Option<FieldAttr>,
+    /// Architecture features required for the field to exist, e.g. `FEAT_PAN`
+    pub features: Vec<String>,
+}
+
+impl BitfieldDesc {
+    pub fn width(&self) -> u32 {
+        self.to - self.from + 1
+    }
+
+    /// Anonymous ranges are padding inserted by `RegisterDesc::from_reg`
+    /// or unnamed bitfields in the specification.
+    pub fn is_padding(&self) -> bool {
+        self.name.as_ref().is_none_or(|x| x.is_empty())
+    }
+
+    /// Bit position as shown in register tables, e.g. `31` or `26..24`
+    pub fn bits_label(&self) -> String {
+        if self.from == self.to {
+            format!("{}", self.to)
+        } else {
+            format!("{}..{}", self.to, self.from)
+        }
+    }
+
+    /// Meaning of the field value if it's one of the named values
+    pub fn meaning(&self, value: u64) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|x| x.value == value)
+            .map(|x| x.meaning.as_str())
+    }
+
+    /// Label for the names row, reserved padding is shown by its attribute
+    pub fn label(&self) -> String {
+        match (&self.name, self.attr) {
+            (Some(name), _) if !name.is_empty() => name.clone(),
+            (_, Some(attr)) => attr.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Whether register value contradicts RES0, RES1 or RAZ/WI attribute
+    pub fn is_violated(&self, value: u64) -> bool {
+        let x = self.extract(value);
+        match self.attr {
+            Some(FieldAttr::Res0) | Some(FieldAttr::RazWi) => x != 0,
+            Some(FieldAttr::Res1) if self.from < 64 => x != self.extract(u64::MAX),
+            _ => false,
+        }
+    }
+
+    /// Replace the field in register `value` with `x`, extra bits of `x`
+    /// are dropped
+    pub fn insert(&self, value: u64, x: u64) -> u64 {
+        if self.from >= 64 {
+            return value;
+        }
+
+        let mask = self.extract(u64::MAX) << self.from;
+        (value & !mask) | ((x << self.from) & mask)
+    }
+
+    /// Extract field value from the register value
+    pub fn extract(&self, value: u64) -> u64 {
+        if self.from >= 64 {
+            return 0;
+        }
+
+        let value = value >> self.from;
+        if self.width() >= 64 {
+            value
+        } else {
+            value & ((1 << self.width()) - 1)
+        }
+    }
+}
+
+/// Layout of bits `from..=to` used instead of the default one when the
+/// `discriminator` field holds one of `values`, e.g. `ESR_EL1.ISS` of a
+/// Data Abort
+#[derive(Debug, Clone, Default)]
+pub struct LayoutVariant {
+    pub name: String,
+    pub discriminator: String,
+    pub values: Vec<u64>,
+    pub from: u32,
+    pub to: u32,
+    /// Fields covering `from..=to` with padding, most significant first
+    pub fields: Vec<BitfieldDesc>,
 }
 
-#[derive(Debug)]
+/// Field holding different values in two register values
+#[derive(Debug, Clone)]
+pub struct FieldDiff {
+    pub field: BitfieldDesc,
+    pub old: u64,
+    pub new: u64,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct RegisterDesc {
     pub name: String,
     pub bits: u32,
     pub fields: Vec<BitfieldDesc>,
     pub value: Option<u64>,
+    pub encoding: Option<encoding::Encoding>,
+    pub variants: Vec<LayoutVariant>,
+    /// Architecture features required for the register to exist
+    pub features: Vec<String>,
 }
 
 impl RegisterDesc {
@@ -215,6 +376,280 @@ impl RegisterDesc {
         true
     }
 
+    /// Set attribute of bits `from..=to`. Padding partially overlapping the
+    /// range is split so only the covered part gets the attribute, a named
+    /// field gets it if the range matches its bits exactly. Names of other
+    /// named fields overlapping the range, left unchanged, are returned.
+    pub fn set_attr(&mut self, from: u32, to: u32, attr: FieldAttr) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut skipped = Vec::new();
+
+        for field in self.fields.drain(..) {
+            if field.from > to || field.to < from {
+                fields.push(field);
+                continue;
+            }
+            if !field.is_padding() {
+                if field.from == from && field.to == to {
+                    fields.push(BitfieldDesc {
+                        attr: Some(attr),
+                        ..field
+                    });
+                } else {
+                    skipped.push(field.label());
+                    fields.push(field);
+                }
+                continue;
+            }
+
+            /* Fields are ordered from the most significant one */
+            let bounds = [
+                (to + 1, field.to),
+                (from.max(field.from), to.min(field.to)),
+                (field.from, from.saturating_sub(1)),
+            ];
+            for (i, (lo, hi)) in bounds.into_iter().enumerate() {
+                if lo > hi || (i == 2 && from == 0) {
+                    continue;
+                }
+                fields.push(BitfieldDesc {
+                    from: lo,
+                    to: hi,
+                    ..field.clone()
+                });
+            }
+        }
+
+        for field in &mut fields {
+            if field.is_padding() && field.from >= from && field.to <= to {
+                field.attr = Some(attr);
+            }
+        }
+
+        self.fields = fields;
+        skipped
+    }
+
+    /// Field with given name of layout variant `variant` or, without it, of
+    /// the only layout defining the name. Names are compared
+    /// case-insensitively, a name defined by several layouts is an error.
+    pub fn field_mut(
+        &mut self,
+        variant: Option<&str>,
+        name: &str,
+    ) -> Result<Option<&mut BitfieldDesc>, String> {
+        let is_named = |x: &BitfieldDesc| {
+            x.name
+                .as_ref()
+                .is_some_and(|x| x.eq_ignore_ascii_case(name))
+        };
+
+        if let Some(variant) = variant {
+            let variant = self
+                .variants
+                .iter_mut()
+                .find(|x| x.name.eq_ignore_ascii_case(variant));
+            return Ok(variant.and_then(|v| v.fields.iter_mut().find(|x| is_named(x))));
+        }
+
+        let layouts = usize::from(self.fields.iter().any(is_named))
+            + self
+                .variants
+                .iter()
+                .filter(|v| v.fields.iter().any(is_named))
+                .count();
+        if layouts > 1 {
+            return Err(format!(
+                "Field {} is defined by {} layouts of {}, select one with {}[\"<variant>\"].{}",
+                name, layouts, self.name, self.name, name
+            ));
+        }
+
+        Ok(self
+            .fields
+            .iter_mut()
+            .chain(self.variants.iter_mut().flat_map(|x| x.fields.iter_mut()))
+            .find(|x| is_named(x)))
+    }
+
+    /// Field by case-insensitive name, bit number (`23`) or bit range
+    /// (`15:14`). Fields of the variant selected by the value come first.
+    pub fn field(&self, target: &str) -> Option<BitfieldDesc> {
+        let by_name = |x: &BitfieldDesc| {
+            x.name
+                .as_ref()
+                .is_some_and(|x| x.eq_ignore_ascii_case(target))
+        };
+        if let Some(field) = self
+            .resolve()
+            .fields
+            .into_iter()
+            .chain(self.fields.iter().cloned())
+            .find(by_name)
+        {
+            return Some(field);
+        }
+
+        let (to, from) = match target.split_once(':') {
+            Some((to, from)) => (to.trim().parse().ok()?, from.trim().parse().ok()?),
+            None => (target.parse().ok()?, target.parse().ok()?),
+        };
+        if from > to || to >= self.bits {
+            return None;
+        }
+
+        Some(BitfieldDesc {
+            from,
+            to,
+            ..Default::default()
+        })
+    }
+
+    /// Register value with `target` field set to `value`, one of the field's
+    /// named values or an expression. Unset register value reads as zero.
+    pub fn assign(&self, target: &str, value: &str) -> Result<u64, String> {
+        let field = self
+            .field(target)
+            .ok_or_else(|| format!("Unknown field: {}", target))?;
+        let named = field
+            .values
+            .iter()
+            .find(|x| x.meaning.eq_ignore_ascii_case(value))
+            .map(|x| x.value);
+        let x = match named {
+            Some(x) => x,
+            None => value.parse::<expr::Expr>()?.eval(Some(self))?,
+        };
+
+        if x & !field.extract(u64::MAX) != 0 {
+            return Err(format!(
+                "Value {:#x} doesn't fit into {} bits {}",
+                x,
+                field.label(),
+                field.bits_label()
+            ));
+        }
+        Ok(field.insert(self.value.unwrap_or(0), x))
+    }
+
+    /// Fields differing between `old` and `new` values, most significant
+    /// first. Variant layout is used only if both values select it.
+    pub fn diff(&self, old: u64, new: u64) -> Vec<FieldDiff> {
+        let layout = |value| {
+            RegisterDesc {
+                value: Some(value),
+                ..self.clone()
+            }
+            .resolve()
+            .fields
+        };
+        let same_variant = match (self.variant(old), self.variant(new)) {
+            (Some(a), Some(b)) => std::ptr::eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        let fields = if same_variant {
+            layout(old)
+        } else {
+            self.fields.clone()
+        };
+
+        fields
+            .into_iter()
+            .filter(|x| x.extract(old) != x.extract(new))
+            .map(|field| FieldDiff {
+                old: field.extract(old),
+                new: field.extract(new),
+                field,
+            })
+            .collect()
+    }
+
+    /// Layout variant selected by the discriminator field of `value`
+    pub fn variant(&self, value: u64) -> Option<&LayoutVariant> {
+        self.variants.iter().find(|variant| {
+            self.fields
+                .iter()
+                .filter(|x| {
+                    x.name
+                        .as_ref()
+                        .is_some_and(|x| x.eq_ignore_ascii_case(&variant.discriminator))
+                })
+                .any(|x| variant.values.contains(&x.extract(value)))
+        })
+    }
+
+    /// Register as implemented with `profile`: fields requiring missing
+    /// features become padding
+    pub fn with_features(&self, profile: &features::FeatureSet) -> RegisterDesc {
+        let mut reg = self.clone();
+        for field in &mut reg.fields {
+            if !profile.has_all(&field.features) {
+                *field = BitfieldDesc {
+                    from: field.from,
+                    to: field.to,
+                    ..Default::default()
+                };
+            }
+        }
+        reg
+    }
+
+    /// Register with fields of the variant selected by its value, or the
+    /// default layout when there is no value or no matching variant
+    pub fn resolve(&self) -> RegisterDesc {
+        let mut reg = self.clone();
+        let Some(variant) = self.value.and_then(|x| self.variant(x)) else {
+            return reg;
+        };
+
+        reg.fields = self
+            .fields
+            .iter()
+            .filter(|x| x.from > variant.to)
+            .chain(&variant.fields)
+            .chain(self.fields.iter().filter(|x| x.to < variant.from))
+            .cloned()
+            .collect();
+        reg
+    }
+
+    /// Split fields into lanes of `lane_bits`, lowest lane first. Fields
+    /// crossing a lane boundary are cut into several parts which keep the
+    /// original name.
+    pub fn split_lanes(&self, lane_bits: u32) -> Vec<Vec<BitfieldDesc>> {
+        let count = self.bits.div_ceil(lane_bits).max(1);
+        let mut lanes = vec![Vec::new(); count as usize];
+
+        for field in &self.fields {
+            let mut to = field.to;
+            loop {
+                let lane = to / lane_bits;
+                let from = field.from.max(lane * lane_bits);
+
+                /* Named values describe the whole field, not its parts */
+                let values = if from == field.from && to == field.to {
+                    field.values.clone()
+                } else {
+                    Vec::new()
+                };
+
+                lanes[lane as usize].push(BitfieldDesc {
+                    from,
+                    to,
+                    values,
+                    ..field.clone()
+                });
+
+                if from == field.from {
+                    break;
+                }
+                to = from - 1;
+            }
+        }
+
+        lanes
+    }
+
     pub fn from_reg(reg: &crate::ast::Register) -> Self {
         let mut fields = Vec::new();
         let mut expected = Some(reg.bits - 1);
@@ -225,7 +660,7 @@ impl RegisterDesc {
                 fields.push(BitfieldDesc {
                     from: f.to + 1,
                     to: x,
-                    name: None,
+                    ..Default::default()
                 })
             }
 
@@ -233,6 +668,7 @@ impl RegisterDesc {
                 from: f.from,
                 to: f.to,
                 name: Some(f.name.to_string()),
+                ..Default::default()
             });
 
             expected = f.from.checked_sub(1);
@@ -243,7 +679,7 @@ impl RegisterDesc {
             fields.push(BitfieldDesc {
                 from: 0,
                 to: x,
-                name: None,
+                ..Default::default()
             })
         }
 
@@ -251,92 +687,16 @@ impl RegisterDesc {
             name: reg.name.to_string(),
             bits: reg.bits,
             fields,
-            value: None,
+            ..Default::default()
         }
     }
 }
 
 impl fmt::Display for RegisterDesc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut names = Vec::new();
-        let mut ranges = Vec::new();
-        let mut values = Vec::new();
-        enum Row {
-            Names,
-            Bits,
-            Values,
-        }
-
         writeln!(f, "{}", self.name)?;
-
-        for field in &self.fields {
-            names.push(format! {" {} ", field.name.as_ref().unwrap_or(&String::new())});
-            ranges.push(if field.from == field.to {
-                format!(" {} ", field.to)
-            } else {
-                format!(" {}..{} ", field.to, field.from)
-            });
-            let mask_shift = field.to - field.from;
-
-            if self.value.is_none() {
-                values.push(String::new());
-                continue;
-            }
-
-            let mask = 1 << mask_shift;
-            let mask = (mask | (mask - 1)) << field.from;
-
-            let val = (mask & self.value.unwrap_or(0)) >> field.from;
-            values.push(format!(" {} ", val));
-        }
-
-        let print_row = |f: &mut fmt::Formatter, index: Row| -> fmt::Result {
-            for ((a, b), c) in names.iter().zip(&ranges).zip(&values) {
-                let size = max(a.len(), b.len());
-                let size = max(c.len(), size);
-                let d = match &index {
-                    Row::Names => a,
-                    Row::Bits => b,
-                    Row::Values => c,
-                };
-
-                let offset = (size - d.len()) / 2;
-                write!(f, "|")?;
-                for _ in 0..offset {
-                    write!(f, " ")?;
-                }
-                write!(f, "{}", d)?;
-
-                for _ in offset + d.len()..size {
-                    write!(f, " ")?;
-                }
-            }
-
-            writeln!(f, "|")
-        };
-
-        let print_line = |f: &mut fmt::Formatter| -> fmt::Result {
-            for ((a, b), c) in names.iter().zip(&ranges).zip(&values) {
-                let size = max(a.len(), b.len());
-                let size = max(c.len(), size);
-                write!(f, "+")?;
-                for _ in 0..size {
-                    write!(f, "-")?;
-                }
-            }
-            writeln!(f, "+")
-        };
-
-        print_line(f)?;
-        print_row(f, Row::Bits)?;
-        print_line(f)?;
-        print_row(f, Row::Names)?;
-        print_line(f)?;
-        if self.value.is_some() {
-            print_row(f, Row::Values)?;
-            print_line(f)?;
-        }
-        Ok(())
+        let reg = self.resolve();
+        render::write_table(f, &reg.fields, reg.value, &Default::default())
     }
 }
 
@@ -376,9 +736,11 @@ fn verify_conversion(reg: &crate::ast::Register, reg_desc: &RegisterDesc) {
 }
 */
 
-pub fn parse_registers(input: &str) -> BTreeMap<String, RegisterDesc> {
+/// Registers described by `input` and the number of invalid registers
+/// skipped, or the syntax error
+pub fn try_parse_registers(input: &str) -> Result<(BTreeMap<String, RegisterDesc>, usize), String> {
     let parser = registers::ProgramParser::new();
-    let program = parser.parse(input).unwrap();
+    let program = parser.parse(input).map_err(|e| e.to_string())?;
 
     let mut data = BTreeMap::new();
 
@@ -399,7 +761,13 @@ pub fn parse_registers(input: &str) -> BTreeMap<String, RegisterDesc> {
         }
     }
 
-    println!("Skipped {} registers", skip_counter);
+    Ok((data, skip_counter))
+}
+
+pub fn parse_registers(input: &str) -> BTreeMap<String, RegisterDesc> {
+    let (data, skip_counter) = try_parse_registers(input).unwrap();
+
+    eprintln!("Skipped {} registers", skip_counter);
 
     data
 }
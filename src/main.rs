@@ -1,13 +1,268 @@
-use std::{env::args, fs::File, io::Read};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::File,
+    io::{self, IsTerminal, Read},
+    path::PathBuf,
+    process,
+};
 
-use asl_parser::parse_registers;
+use asl_parser::{
+    batch::decode_dump,
+    diagram, doc,
+    encoding::{decode_insn, find_register},
+    esr::decode_esr,
+    features::FeatureSet,
+    ipxact::to_ipxact,
+    meta::{apply_meta, BUILTIN_META},
+    parse_registers,
+    render::{render, render_diff, Charset, Layout, Radix, RenderOptions},
+    report::{self, fields_text, register_json, registers_csv},
+    search::{search, FieldIndex, Query},
+    try_parse_registers, RegisterDesc,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use terminal_size::{terminal_size, Width};
 
 mod asl_helpers;
-use asl_helpers::{build_regs_asl, regs_asl_path};
+use asl_helpers::{build_regs_asl, impdef_asl_path, regs_asl_path, regs_meta_path};
+use screen::run_screen;
 use tui_fsm::run_tui;
 
-mod tui_fsm;
 mod prefix_fsm;
+mod screen;
+mod tui_fsm;
+
+#[derive(Parser)]
+#[command(about = "Arm system registers decoder")]
+struct Cli {
+    /// Register layout: auto, table, chunked32, chunked16 or vertical
+    #[arg(long, default_value = "auto")]
+    layout: Layout,
+    /// Table style, fancy uses box-drawing characters and colors
+    /// when stdout is a terminal and NO_COLOR is not set
+    #[arg(long, value_enum, default_value_t = Style::Plain)]
+    style: Style,
+    /// Radix of field values: dec, hex, bin or auto (by field width)
+    #[arg(long, default_value = "dec")]
+    radix: Radix,
+    /// Radix override for a single field, e.g. M=bin
+    #[arg(long, value_parser = parse_field_radix)]
+    field_radix: Vec<(String, Radix)>,
+    /// Features of the target core, e.g. "armv8.2 + SVE". Fields and
+    /// registers requiring other features are marked with ?
+    #[arg(long)]
+    features: Option<FeatureSet>,
+    /// Hide fields and registers requiring features missing from --features
+    #[arg(long, requires = "features")]
+    hide_absent: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Style {
+    Plain,
+    Fancy,
+}
+
+/// Output format of scripting commands
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl From<ReportFormat> for report::Format {
+    fn from(format: ReportFormat) -> Self {
+        match format {
+            ReportFormat::Text => report::Format::Text,
+            ReportFormat::Json => report::Format::Json,
+            ReportFormat::Csv => report::Format::Csv,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum DocFormat {
+    #[value(name = "md", alias = "markdown")]
+    Markdown,
+    Html,
+}
+
+impl From<DocFormat> for doc::Format {
+    fn from(format: DocFormat) -> Self {
+        match format {
+            DocFormat::Markdown => doc::Format::Markdown,
+            DocFormat::Html => doc::Format::Html,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum DiagramFormat {
+    Svg,
+    #[value(name = "wavedrom")]
+    WaveDrom,
+}
+
+impl From<DiagramFormat> for diagram::Format {
+    fn from(format: DiagramFormat) -> Self {
+        match format {
+            DiagramFormat::Svg => diagram::Format::Svg,
+            DiagramFormat::WaveDrom => diagram::Format::WaveDrom,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build regs.asl from Arm's specification and start interactive mode
+    Init,
+    /// Full-screen interactive mode with bit toggling
+    Screen,
+    /// Export registers as IP-XACT component to a file or stdout
+    ExportIpxact { file: Option<PathBuf> },
+    /// Generate register reference pages
+    Doc {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = DocFormat::Markdown)]
+        format: DocFormat,
+        out_dir: PathBuf,
+    },
+    /// Draw register diagram to stdout
+    Diagram {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = DiagramFormat::Svg)]
+        format: DiagramFormat,
+        register: String,
+        /// Register value to decode in the diagram
+        #[arg(value_parser = parse_number)]
+        value: Option<u64>,
+    },
+    /// Show fields differing between two register values
+    Diff {
+        register: String,
+        #[arg(value_parser = parse_number)]
+        old: u64,
+        #[arg(value_parser = parse_number)]
+        new: u64,
+    },
+    /// Decode exception syndrome value
+    Esr {
+        /// Syndrome register providing the layout
+        #[arg(long, default_value = "ESR_EL1")]
+        register: String,
+        #[arg(value_parser = parse_number)]
+        value: u64,
+    },
+    /// Decode MRS/MSR/MRC/MCR instruction word
+    DecodeInsn {
+        #[arg(value_parser = parse_number)]
+        insn: u64,
+    },
+    /// Show register layout
+    Show {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        register: String,
+    },
+    /// Decode register value
+    Decode {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        register: String,
+        #[arg(value_parser = parse_number)]
+        value: u64,
+    },
+    /// Build register value from field assignments, e.g. M=1 EE=0b1
+    Encode {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// Value the assignments are applied to
+        #[arg(long, value_parser = parse_number, default_value = "0")]
+        base: u64,
+        register: String,
+        #[arg(required = true)]
+        fields: Vec<String>,
+    },
+    /// List registers matching a name, .FIELD, glob or /regex/ pattern
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        pattern: Option<String>,
+    },
+    /// Decode `name=value`, `name: value` or `name value` lines of a
+    /// register dump, read from stdin when no file is given
+    Batch {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// Read values without 0x, 0o or 0b prefix as decimal instead of hex
+        #[arg(long)]
+        decimal: bool,
+        file: Option<PathBuf>,
+    },
+    /// List fields of a register
+    Fields {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        register: String,
+    },
+    /// Show registers having a field with the field bits, fields are
+    /// matched by name or, failing that, by description
+    Field {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        name: String,
+    },
+}
+
+/// Exit code of unknown register or no matches
+const EXIT_NOT_FOUND: i32 = 1;
+/// Exit code of invalid value, field assignment or pattern
+const EXIT_INVALID: i32 = 2;
+
+fn fail(code: i32, msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(code)
+}
+
+fn lookup<'a>(data: &'a BTreeMap<String, RegisterDesc>, name: &str) -> &'a RegisterDesc {
+    find_register(data, name)
+        .unwrap_or_else(|| fail(EXIT_NOT_FOUND, &format!("Unknown register: {}", name)))
+}
+
+fn print_register(reg: &RegisterDesc, format: report::Format, opts: &RenderOptions) {
+    match format {
+        report::Format::Text => print!("{}", render(reg, opts)),
+        report::Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&register_json(reg)).expect("Can't serialize register")
+        ),
+        report::Format::Csv => print!("{}", registers_csv(std::slice::from_ref(reg))),
+    }
+}
+
+fn parse_field_radix(input: &str) -> Result<(String, Radix), String> {
+    let (field, radix) = input
+        .split_once('=')
+        .ok_or_else(|| format!("Expected FIELD=RADIX, got {}", input))?;
+
+    Ok((field.to_lowercase(), radix.parse()?))
+}
+
+/// Number with `0x`, `0o` or `0b` prefix or decimal
+fn parse_number(input: &str) -> Result<u64, String> {
+    asl_parser::expr::parse_number(input, 10)
+}
 
 fn init_state() -> File {
     let path = regs_asl_path();
@@ -20,8 +275,9 @@ fn init_state() -> File {
 
 #[tokio::main]
 async fn main() {
-    let args: Vec<_> = args().collect();
-    if args.len() > 1 && args[1] == "init" {
+    let cli = Cli::parse();
+
+    if let Some(Command::Init) = cli.command {
         if let Err(e) = build_regs_asl().await {
             panic!("Can't initialize regs.asl: {}", e);
         }
@@ -33,7 +289,218 @@ async fn main() {
     file.read_to_string(&mut input)
         .expect("Can't open regs.asl");
 
-    let data = parse_registers(&input);
+    let mut data = parse_registers(&input);
+
+    if let Ok(impdef) = std::fs::read_to_string(impdef_asl_path()) {
+        match try_parse_registers(&impdef) {
+            Ok((regs, 0)) => data.extend(regs),
+            Ok((regs, skipped)) => {
+                eprintln!(
+                    "Skipped {} registers in {}",
+                    skipped,
+                    impdef_asl_path().display()
+                );
+                data.extend(regs);
+            }
+            Err(e) => eprintln!("Can't parse {}: {}", impdef_asl_path().display(), e),
+        }
+    }
 
-    run_tui(&data).expect("Error while interacting with user");
+    match apply_meta(&mut data, BUILTIN_META) {
+        Ok(warnings) => {
+            for warning in warnings {
+                eprintln!("Skipped built-in metadata: {}", warning);
+            }
+        }
+        Err(e) => eprintln!("Can't parse built-in metadata: {}", e),
+    }
+    if let Ok(meta) = std::fs::read_to_string(regs_meta_path()) {
+        match apply_meta(&mut data, &meta) {
+            Ok(warnings) => {
+                for warning in warnings {
+                    eprintln!("Skipped in {}: {}", regs_meta_path().display(), warning);
+                }
+            }
+            Err(e) => eprintln!("Can't parse {}: {}", regs_meta_path().display(), e),
+        }
+    }
+    /* Metadata adds variant fields and descriptions, index them afterwards */
+    let index = FieldIndex::new(&data);
+
+    let fancy = cli.style == Style::Fancy
+        && io::stdout().is_terminal()
+        && env::var_os("NO_COLOR").is_none();
+
+    let opts = RenderOptions {
+        layout: cli.layout,
+        charset: if fancy {
+            Charset::Unicode
+        } else {
+            Charset::Ascii
+        },
+        color: fancy,
+        radix: cli.radix,
+        field_radix: cli.field_radix.into_iter().collect(),
+        features: cli.features,
+        hide_absent: cli.hide_absent,
+        /* Auto layout fits the terminal, output to pipes and files is not cut */
+        width: io::stdout()
+            .is_terminal()
+            .then(terminal_size)
+            .flatten()
+            .map(|(Width(w), _)| w as usize),
+        ..Default::default()
+    };
+
+    match cli.command {
+        Some(Command::ExportIpxact { file }) => {
+            let xml = to_ipxact(&data);
+            match file {
+                Some(path) => std::fs::write(path, xml).expect("Can't write IP-XACT file"),
+                None => print!("{}", xml),
+            }
+        }
+        Some(Command::Doc { format, out_dir }) => {
+            doc::write_docs(&data, format.into(), &out_dir).expect("Can't write documentation");
+        }
+        Some(Command::Diagram {
+            format,
+            register,
+            value,
+        }) => {
+            let reg = RegisterDesc {
+                value,
+                ..lookup(&data, &register).clone()
+            };
+            print!("{}", diagram::render(&reg, format.into()));
+        }
+        Some(Command::DecodeInsn { insn }) => {
+            match u32::try_from(insn).ok().and_then(decode_insn) {
+                Some(access) => {
+                    println!("{}", access.disassemble(&data));
+                    println!(
+                        "{} {}",
+                        if access.write { "Write" } else { "Read" },
+                        access.encoding
+                    );
+                }
+                None => fail(
+                    EXIT_INVALID,
+                    &format!("Not a system register access: {:#x}", insn),
+                ),
+            }
+        }
+        Some(Command::Diff { register, old, new }) => {
+            print!("{}", render_diff(lookup(&data, &register), old, new, &opts))
+        }
+        Some(Command::Esr { register, value }) => match decode_esr(&data, &register, value) {
+            Some(syndrome) => print!("{}", syndrome.render(&opts)),
+            None => fail(
+                EXIT_NOT_FOUND,
+                &format!("Unknown syndrome register: {}", register),
+            ),
+        },
+        Some(Command::Show { format, register }) => {
+            print_register(lookup(&data, &register), format.into(), &opts)
+        }
+        Some(Command::Decode {
+            format,
+            register,
+            value,
+        }) => {
+            let reg = RegisterDesc {
+                value: Some(value),
+                ..lookup(&data, &register).clone()
+            };
+            print_register(&reg, format.into(), &opts);
+        }
+        Some(Command::Encode {
+            format,
+            base,
+            register,
+            fields,
+        }) => {
+            let format = report::Format::from(format);
+            let mut reg = RegisterDesc {
+                value: Some(base),
+                ..lookup(&data, &register).clone()
+            };
+            for assignment in &fields {
+                let (field, value) = assignment.split_once('=').unwrap_or_else(|| {
+                    fail(
+                        EXIT_INVALID,
+                        &format!("Expected FIELD=VALUE, got {}", assignment),
+                    )
+                });
+                match reg.assign(field.trim(), value.trim()) {
+                    Ok(x) => reg.value = Some(x),
+                    Err(e) => fail(EXIT_INVALID, &e),
+                }
+            }
+
+            match format {
+                report::Format::Text => println!("{:#x}", reg.value.unwrap_or(0)),
+                _ => print_register(&reg, format, &opts),
+            }
+        }
+        Some(Command::List { format, pattern }) => {
+            let format = report::Format::from(format);
+            let regs: Vec<_> = match &pattern {
+                Some(pattern) => match pattern.parse::<Query>() {
+                    Ok(query) => search(&data, &index, &query),
+                    Err(e) => fail(EXIT_INVALID, &e),
+                },
+                None => data.values().collect(),
+            };
+            print!("{}", report::list(&regs, format));
+            if regs.is_empty() {
+                process::exit(EXIT_NOT_FOUND);
+            }
+        }
+        Some(Command::Batch {
+            format,
+            decimal,
+            file,
+        }) => {
+            let format = report::Format::from(format);
+            let input = match &file {
+                Some(path) if path.as_os_str() != "-" => std::fs::read_to_string(path),
+                _ => io::read_to_string(io::stdin()),
+            };
+            let input =
+                input.unwrap_or_else(|e| fail(EXIT_INVALID, &format!("Can't read dump: {}", e)));
+
+            let dump = decode_dump(&data, &input, if decimal { 10 } else { 16 });
+            print!("{}", dump.render(format, &opts));
+            if format != report::Format::Json {
+                for diagnostic in &dump.diagnostics {
+                    eprintln!("{}", diagnostic);
+                }
+            }
+            if !dump.diagnostics.is_empty() {
+                process::exit(EXIT_INVALID);
+            }
+        }
+        Some(Command::Fields { format, register }) => {
+            let format = report::Format::from(format);
+            let reg = lookup(&data, &register);
+            match format {
+                report::Format::Text => print!("{}", fields_text(reg)),
+                _ => print_register(reg, format, &opts),
+            }
+        }
+        Some(Command::Field { format, name }) => {
+            let found = index.find(&name);
+            print!("{}", report::locations(&found, format.into()));
+            if found.is_empty() {
+                process::exit(EXIT_NOT_FOUND);
+            }
+        }
+        Some(Command::Screen) => {
+            run_screen(&data, &opts).expect("Error while interacting with user");
+        }
+        Some(Command::Init) | None => {
+            run_tui(&data, &index, &opts).expect("Error while interacting with user");
+        }
+    }
 }
@@ -0,0 +1,489 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use crate::{
+    ast::{FeaturesTarget, FieldRef, Meta, Variant, VariantTarget},
+    encoding::Encoding,
+    meta_parser, BitfieldDesc, FieldValueDesc, LayoutVariant, RegisterDesc,
+};
+
+fn make_encoding(coproc: Option<&str>, operands: &[u64]) -> Result<Encoding, String> {
+    let operands: Vec<u8> = operands
+        .iter()
+        .map(|&x| u8::try_from(x).map_err(|_| format!("Encoding operand {} is too big", x)))
+        .collect::<Result<_, _>>()?;
+
+    match (coproc, &operands[..]) {
+        (None, &[op0, op1, crn, crm, op2]) => Ok(Encoding::AArch64 {
+            op0,
+            op1,
+            crn,
+            crm,
+            op2,
+        }),
+        (Some(cp), &[opc1, crn, crm, opc2]) => {
+            let coproc = cp
+                .strip_prefix('p')
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(|| format!("Invalid coprocessor: {}", cp))?;
+            Ok(Encoding::AArch32 {
+                coproc,
+                opc1,
+                crn,
+                crm,
+                opc2,
+            })
+        }
+        _ => Err(format!(
+            "Expected (op0, op1, CRn, CRm, op2) or pN (opc1, CRn, CRm, opc2), got {:?}",
+            operands
+        )),
+    }
+}
+
+/*
+ * Variant replaces whole fields of the default layout, so its range must not
+ * cut any of them. Gaps between variant fields become padding.
+ */
+fn make_variant(reg: &RegisterDesc, variant: &Variant) -> Result<Option<LayoutVariant>, String> {
+    let (from, to) = match &variant.target {
+        VariantTarget::Field(f) if f.variant.is_some() => {
+            return Err(format!(
+                "Variant of variant field {} of {} isn't supported",
+                f.field, reg.name
+            ));
+        }
+        VariantTarget::Field(f) => {
+            let field = reg.fields.iter().find(|x| {
+                x.name
+                    .as_ref()
+                    .is_some_and(|x| x.eq_ignore_ascii_case(f.field))
+            });
+            match field {
+                Some(field) => (field.from, field.to),
+                None => return Ok(None),
+            }
+        }
+        VariantTarget::Range(_, to, from) => (*from, *to),
+    };
+
+    if !reg.fields.iter().any(|x| {
+        x.name
+            .as_ref()
+            .is_some_and(|x| x.eq_ignore_ascii_case(variant.discriminator))
+    }) {
+        return Err(format!(
+            "Unknown discriminator {} of variant \"{}\" of {}",
+            variant.discriminator, variant.name, reg.name
+        ));
+    }
+
+    if from > to || to >= reg.bits {
+        return Err(format!(
+            "Invalid variant range {}:{} of {}",
+            to, from, reg.name
+        ));
+    }
+    if let Some(x) = reg
+        .fields
+        .iter()
+        .find(|x| x.from <= to && x.to >= from && (x.from < from || x.to > to))
+    {
+        return Err(format!(
+            "Variant range {}:{} cuts field {} of {}",
+            to,
+            from,
+            x.bits_label(),
+            reg.name
+        ));
+    }
+
+    let mut bitfields: Vec<_> = variant.fields.iter().collect();
+    bitfields.sort_by_key(|x| Reverse(x.to));
+
+    let mut fields = Vec::new();
+    /* Highest bit not taken yet, none once the fields reach the range end */
+    let mut expected = Some(to);
+    for f in bitfields {
+        /* Fields overlapping previous ones or outside the range don't fit */
+        let top = match expected {
+            Some(x) if f.from <= f.to && f.to <= x && f.from >= from => x,
+            _ => {
+                return Err(format!(
+                    "Variant field {} doesn't fit {}:{} of {}",
+                    f.name, to, from, reg.name
+                ));
+            }
+        };
+        if f.to < top {
+            fields.push(BitfieldDesc {
+                from: f.to + 1,
+                to: top,
+                ..Default::default()
+            });
+        }
+        fields.push(BitfieldDesc {
+            from: f.from,
+            to: f.to,
+            name: Some(f.name.to_string()),
+            ..Default::default()
+        });
+        expected = f.from.checked_sub(1).filter(|&x| x >= from);
+    }
+    if fields.last().is_none_or(|x| x.from > from) {
+        fields.push(BitfieldDesc {
+            from,
+            to: fields.last().map_or(to, |x| x.from - 1),
+            ..Default::default()
+        });
+    }
+
+    Ok(Some(LayoutVariant {
+        name: variant.name.to_string(),
+        discriminator: variant.discriminator.to_string(),
+        values: variant.values.clone(),
+        from,
+        to,
+        fields,
+    }))
+}
+
+/// Registers named `name` or, for `NAME_ELx`, `NAME_EL1`, `NAME_EL2` and
+/// `NAME_EL3`
+fn registers_mut<'a>(
+    data: &'a mut BTreeMap<String, RegisterDesc>,
+    name: &str,
+) -> Vec<&'a mut RegisterDesc> {
+    let name = name.to_lowercase();
+    let names: Vec<_> = match name.strip_suffix("_elx") {
+        Some(prefix) => (1..=3).map(|el| format!("{}_el{}", prefix, el)).collect(),
+        None => vec![name],
+    };
+
+    data.iter_mut()
+        .filter(|(k, _)| names.contains(k))
+        .map(|x| x.1)
+        .collect()
+}
+
+/// Field referenced by metadata, a name defined by several layouts is
+/// reported as a warning
+fn find_field<'a>(
+    reg: &'a mut RegisterDesc,
+    f: &FieldRef,
+    warnings: &mut Vec<String>,
+) -> Option<&'a mut BitfieldDesc> {
+    reg.field_mut(f.variant, f.field).unwrap_or_else(|e| {
+        warnings.push(e);
+        None
+    })
+}
+
+/// Metadata shipped with the crate, see `src/builtin.meta`
+pub const BUILTIN_META: &str = include_str!("builtin.meta");
+
+/*
+ * Register metadata which is not part of regs.asl: named field values, field
+ * descriptions, attributes of reserved bits, instruction encodings,
+ * alternative field layouts and required architecture features.
+ * Entries naming `REG_ELx` apply to REG_EL1, REG_EL2 and REG_EL3, entries for
+ * registers or fields missing from the database are ignored.
+ * Syntax errors fail the whole input, entries which don't fit the register
+ * are skipped and returned as warnings.
+ */
+pub fn apply_meta(
+    data: &mut BTreeMap<String, RegisterDesc>,
+    input: &str,
+) -> Result<Vec<String>, String> {
+    let parser = meta_parser::ProgramParser::new();
+    let program = parser.parse(input).map_err(|e| e.to_string())?;
+    let mut warnings = Vec::new();
+
+    for stmt in program {
+        match stmt {
+            Meta::Values(f, values) => {
+                for reg in registers_mut(data, f.register) {
+                    let reg_name = reg.name.clone();
+                    let Some(field) = find_field(reg, &f, &mut warnings) else {
+                        continue;
+                    };
+
+                    let (fit, wide): (Vec<_>, Vec<_>) = values
+                        .iter()
+                        .partition(|x| field.width() >= 64 || x.value >> field.width() == 0);
+                    for x in wide {
+                        warnings.push(format!(
+                            "Value {:#x} doesn't fit into {}.{} bits {}",
+                            x.value,
+                            reg_name,
+                            field.label(),
+                            field.bits_label()
+                        ));
+                    }
+                    field.values = fit
+                        .into_iter()
+                        .map(|x| FieldValueDesc {
+                            value: x.value,
+                            meaning: x.meaning.to_string(),
+                        })
+                        .collect();
+                }
+            }
+            Meta::Describe(f, description) => {
+                for reg in registers_mut(data, f.register) {
+                    if let Some(field) = find_field(reg, &f, &mut warnings) {
+                        field.description = Some(description.to_string());
+                    }
+                }
+            }
+            Meta::Attrs(register, ranges) => {
+                for reg in registers_mut(data, register) {
+                    for range in &ranges {
+                        if range.from <= range.to && range.to < reg.bits {
+                            for name in reg.set_attr(range.from, range.to, range.attr) {
+                                warnings.push(format!(
+                                    "{} range {}:{} of {} doesn't match the bits of {}, field left unchanged",
+                                    range.attr, range.to, range.from, reg.name, name
+                                ));
+                            }
+                        } else {
+                            warnings.push(format!(
+                                "Invalid {} range {}:{} of {}",
+                                range.attr, range.to, range.from, reg.name
+                            ));
+                        }
+                    }
+                }
+            }
+            Meta::Encoding(register, coproc, operands) => {
+                let encoding = match make_encoding(coproc, &operands) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        warnings.push(format!("{}: {}", register, e));
+                        continue;
+                    }
+                };
+
+                for reg in registers_mut(data, register) {
+                    reg.encoding = Some(encoding);
+                }
+            }
+            Meta::Variant(variant) => {
+                let register = match &variant.target {
+                    VariantTarget::Field(f) => f.register,
+                    VariantTarget::Range(r, _, _) => r,
+                };
+
+                for reg in registers_mut(data, register) {
+                    match make_variant(reg, &variant) {
+                        Ok(Some(variant)) => reg.variants.push(variant),
+                        Ok(None) => {}
+                        Err(e) => warnings.push(e),
+                    }
+                }
+            }
+            Meta::Features(target, features) => {
+                let features: Vec<_> = features.iter().map(|x| x.to_string()).collect();
+
+                match target {
+                    FeaturesTarget::Register(register) => {
+                        for reg in registers_mut(data, register) {
+                            reg.features = features.clone();
+                        }
+                    }
+                    FeaturesTarget::Field(f) => {
+                        for reg in registers_mut(data, f.register) {
+                            if let Some(field) = find_field(reg, &f, &mut warnings) {
+                                field.features = features.clone();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        meta::{apply_meta, BUILTIN_META},
+        parse_registers, FieldAttr, RegisterDesc,
+    };
+
+    #[test]
+    fn test_values() {
+        let mut data = parse_registers("__register 64 { 15:14 TG0, 3:0 M } TCR_EL1;");
+        let input = "
+            // comment
+            __values TCR_EL1.TG0 { 0b00 = \"4KB\", 0x1 = \"64KB\", 2 = \"16KB\", 4 = \"Too wide\" };
+            __values TCR_EL1.Missing { 0 = \"Ignored\" };
+            __values MISSING.M { 0 = \"Ignored\" };
+        ";
+        let warnings = apply_meta(&mut data, input).unwrap();
+        assert!(
+            warnings.contains(&"Value 0x4 doesn't fit into TCR_EL1.TG0 bits 15..14".to_string())
+        );
+
+        let tg0 = &data["tcr_el1"].fields[1];
+        assert_eq!(tg0.meaning(0), Some("4KB"));
+        assert_eq!(tg0.meaning(1), Some("64KB"));
+        assert_eq!(tg0.meaning(3), None);
+        assert_eq!(tg0.values.len(), 3);
+
+        assert!(apply_meta(&mut data, "__values TCR_EL1.TG0 { 0 };").is_err());
+        /* Literals out of range are errors, not panics or truncation */
+        let overflow = "__values TCR_EL1.TG0 { 0x10000000000000000 = \"X\" };";
+        assert!(apply_meta(&mut data, overflow).is_err());
+        assert!(apply_meta(&mut data, "__attrs TCR_EL1 { 4294967296:0 RES0 };").is_err());
+        assert_eq!(apply_meta(&mut data, BUILTIN_META), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_attrs() {
+        let mut data = parse_registers("__register 32 { 31:31 N, 3:0 M } CPSR;");
+        let input = "
+            __describe CPSR.N \"Negative condition flag\";
+            __attrs CPSR { 31:31 RES1, 30:28 RES0, 27:27 RES1, 5:5 RAZ/WI, 40:40 RES0, 6:7 RES0 };
+            __attrs CPSR { 4:0 UNKNOWN };
+        ";
+        let warnings = apply_meta(&mut data, input).unwrap();
+        assert_eq!(
+            warnings,
+            vec![
+                "Invalid RES0 range 40:40 of CPSR".to_string(),
+                "Invalid RES0 range 6:7 of CPSR".to_string(),
+                "UNKNOWN range 4:0 of CPSR doesn't match the bits of M, field left unchanged"
+                    .to_string(),
+            ]
+        );
+
+        let reg = &data["cpsr"];
+        let fields: Vec<_> = reg
+            .fields
+            .iter()
+            .map(|x| (x.to, x.from, x.label()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (31, 31, "N".to_string()),
+                (30, 28, "RES0".to_string()),
+                (27, 27, "RES1".to_string()),
+                (26, 6, String::new()),
+                (5, 5, "RAZ/WI".to_string()),
+                (4, 4, "UNKNOWN".to_string()),
+                (3, 0, "M".to_string()),
+            ]
+        );
+        assert_eq!(
+            reg.fields[0].description.as_deref(),
+            Some("Negative condition flag")
+        );
+
+        /* Named fields take attributes of ranges matching their bits only */
+        assert_eq!(reg.fields[0].attr, Some(FieldAttr::Res1));
+        assert!(reg.fields[0].is_violated(0));
+        assert!(reg.fields[6].attr.is_none());
+        assert!(reg.fields[1].is_violated(1 << 29));
+        assert!(reg.fields[2].is_violated(0));
+        assert!(!reg.fields[2].is_violated(1 << 27));
+        assert!(reg.fields[4].is_violated(1 << 5));
+        assert!(!reg.fields[3].is_violated(u64::MAX));
+    }
+
+    #[test]
+    fn test_variants() {
+        let mut data = parse_registers("__register 64 { 31:26 EC, 25:25 IL, 24:0 ISS } ESR_EL1;");
+        let input = "
+            __variant ESR_EL1.ISS when EC = 0x24, 0x25 \"Data Abort\" { 6:6 WnR, 5:0 DFSC, 24:24 ISV };
+            __variant ESR_EL1.ISS when EC = 0x15 \"SVC\" { 15:0 imm16 };
+            __values ESR_EL1.DFSC { 0b000101 = \"Translation fault, level 1\" };
+        ";
+        apply_meta(&mut data, input).unwrap();
+
+        let reg = &data["esr_el1"];
+        assert!(reg.variant(0x2000000).is_none());
+        assert_eq!(reg.variant(0x24 << 26).unwrap().name, "Data Abort");
+
+        let reg = RegisterDesc {
+            value: Some(0x96000045),
+            ..reg.clone()
+        };
+        let fields: Vec<_> = reg
+            .resolve()
+            .fields
+            .iter()
+            .map(|x| (x.to, x.from, x.label()))
+            .collect();
+        assert_eq!(
+            fields[2..],
+            [
+                (25, 25, "IL".to_string()),
+                (24, 24, "ISV".to_string()),
+                (23, 7, String::new()),
+                (6, 6, "WnR".to_string()),
+                (5, 0, "DFSC".to_string()),
+            ]
+        );
+        let dfsc = &reg.resolve().fields[6];
+        assert_eq!(
+            dfsc.meaning(dfsc.extract(0x96000045)),
+            Some("Translation fault, level 1")
+        );
+
+        let fields: Vec<_> = reg.variants[1]
+            .fields
+            .iter()
+            .map(|x| (x.to, x.from))
+            .collect();
+        assert_eq!(fields, [(24, 16), (15, 0)]);
+
+        /* Variants which don't fit are skipped with a warning */
+        let warnings = apply_meta(
+            &mut data,
+            "__variant ESR_EL1 30:0 when EC = 1 \"Bad\" { };
+             __variant ESR_EL1.ISS when EC = 1 \"Bad\" { 25:0 X };
+             __variant ESR_EL1.ISS when EC = 2 \"Overlap\" { 5:0 A, 3:0 B };
+             __variant ESR_EL1.ISS when XC = 3 \"Unknown\" { 5:0 A };
+             __encoding ESR_EL1 (3, 0, 5);
+             __describe ESR_EL1.EC \"Exception Class\";",
+        )
+        .unwrap();
+        assert_eq!(
+            warnings,
+            [
+                "Variant range 30:0 cuts field 31..26 of ESR_EL1",
+                "Variant field X doesn't fit 24:0 of ESR_EL1",
+                "Variant field B doesn't fit 24:0 of ESR_EL1",
+                "Unknown discriminator XC of variant \"Unknown\" of ESR_EL1",
+                "ESR_EL1: Expected (op0, op1, CRn, CRm, op2) or pN (opc1, CRn, CRm, opc2), got [3, 0, 5]",
+            ]
+        );
+        assert_eq!(data["esr_el1"].variants.len(), 2);
+        assert!(data["esr_el1"].fields[1].description.is_some());
+
+        /* Field defined by several layouts needs the variant name */
+        let warnings = apply_meta(
+            &mut data,
+            "__variant ESR_EL1.ISS when EC = 0x2f \"SError interrupt\" { 5:0 DFSC };
+             __values ESR_EL1.DFSC { 0 = \"Ambiguous\" };
+             __values ESR_EL1[\"SError interrupt\"].DFSC { 0 = \"Uncategorized error\" };
+             __describe ESR_EL1[\"Data Abort\"].DFSC \"Data Fault Status Code\";",
+        )
+        .unwrap();
+        assert_eq!(
+            warnings,
+            [
+                r#"Field DFSC is defined by 2 layouts of ESR_EL1, select one with ESR_EL1["<variant>"].DFSC"#
+            ]
+        );
+        let reg = &data["esr_el1"];
+        let dfsc = reg.variants[0].fields.last().unwrap();
+        assert_eq!(dfsc.meaning(0), None);
+        assert_eq!(dfsc.description.as_deref(), Some("Data Fault Status Code"));
+        let dfsc = reg.variants[2].fields.last().unwrap();
+        assert_eq!(dfsc.meaning(0), Some("Uncategorized error"));
+    }
+}
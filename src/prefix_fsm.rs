@@ -1,7 +1,31 @@
+use asl_parser::expr::Expr;
+
+/// Modification of a single field of the selected value. Field is given by
+/// name, bit number or `to:from` range.
+#[derive(PartialEq, Debug, Clone)]
+pub enum FieldOp {
+    /// `field=value`, value is a number or one of the field's named values
+    Assign(String, String),
+    Set(String),
+    Clear(String),
+    Toggle(String),
+}
+
+impl FieldOp {
+    pub fn field(&self) -> &str {
+        match self {
+            FieldOp::Assign(x, _) | FieldOp::Set(x) | FieldOp::Clear(x) | FieldOp::Toggle(x) => x,
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub enum Event {
     Text(String),
     Number(u64),
+    Field(FieldOp),
+    /// Expression referring to the current value, e.g. `$ | 1`
+    Expr(Expr),
     Empty,
 }
 
@@ -13,16 +37,29 @@ impl Event {
             return Event::Empty;
         }
 
-        if let Some(s) = input.strip_prefix("0x") {
-            if let Ok(num) = u64::from_str_radix(s, 16) {
-                return Event::Number(num);
+        if let Some((field, value)) = input.split_once('=') {
+            let op = FieldOp::Assign(field.trim().to_string(), value.trim().to_string());
+            return Event::Field(op);
+        }
+
+        if let Some((cmd, field)) = input.split_once(char::is_whitespace) {
+            let field = field.trim().to_string();
+            match cmd {
+                "set" => return Event::Field(FieldOp::Set(field)),
+                "clear" => return Event::Field(FieldOp::Clear(field)),
+                "toggle" => return Event::Field(FieldOp::Toggle(field)),
+                _ => (),
             }
         }
 
-        if let Ok(num) = input.parse::<u64>() {
-            Event::Number(num)
-        } else {
-            Event::Text(input)
+        /* Register names are not valid expressions */
+        match input.parse::<Expr>() {
+            Ok(expr) if expr.uses_current() => Event::Expr(expr),
+            Ok(expr) => match expr.eval(None) {
+                Ok(num) => Event::Number(num),
+                Err(_) => Event::Text(input),
+            },
+            Err(_) => Event::Text(input),
         }
     }
 }
@@ -35,7 +72,13 @@ pub enum TState<T> {
 }
 
 pub trait Item {
+    /// Name identifying the item among search results
+    fn name(&self) -> &str;
     fn update(&mut self, x: u64);
+    /// Modify a field of the current value
+    fn apply(&mut self, op: &FieldOp) -> Result<(), String>;
+    /// Evaluate expression with `$` referring to the current value
+    fn eval(&self, expr: &Expr) -> Result<u64, String>;
 }
 
 pub struct Fsm<T, F>
@@ -44,6 +87,12 @@ where
 {
     pub state: TState<T>,
     pub from_prefix: F,
+    /// Why the last event was rejected, state is left unchanged then
+    pub error: Option<String>,
+    /// Previous states, most recent last
+    undo: Vec<TState<T>>,
+    /// States undone since the last transition, most recent last
+    redo: Vec<TState<T>>,
 }
 
 impl<T: Clone + Item, F: Fn(&str) -> Vec<T>> Fsm<T, F> {
@@ -56,9 +105,14 @@ impl<T: Clone + Item, F: Fn(&str) -> Vec<T>> Fsm<T, F> {
     }
 
     pub fn next(&mut self, event: Event) {
-        self.state = match (&self.state, event) {
+        self.error = None;
+        let state = match (&self.state, event) {
             /* From Empty */
             (TState::Empty, Event::Number(_)) => TState::Empty,
+            (TState::Empty, Event::Field(_) | Event::Expr(_)) => {
+                self.error = Some(String::from("No register selected"));
+                TState::Empty
+            }
             (TState::Empty, Event::Text(s)) => {
                 let v = (self.from_prefix)(&s);
                 Self::vec_to_state(s.as_str(), v)
@@ -69,7 +123,38 @@ impl<T: Clone + Item, F: Fn(&str) -> Vec<T>> Fsm<T, F> {
                 Event::Number(x) if (x as usize) < vec.len() => {
                     TState::Selected(vec[x as usize].clone())
                 }
-                _ => TState::Ambiguous(prefix.clone(), vec.clone()),
+                /*
+                 * Typing more narrows the candidates down, text matching none
+                 * of them starts a new search and a miss keeps the candidates
+                 */
+                Event::Text(s) => {
+                    let found = (self.from_prefix)(&s);
+                    let (narrowed, other): (Vec<_>, Vec<_>) = found
+                        .into_iter()
+                        .partition(|x| vec.iter().any(|c| c.name() == x.name()));
+                    match (narrowed, other) {
+                        (v, _) if !v.is_empty() => Self::vec_to_state(s.as_str(), v),
+                        (_, v) if !v.is_empty() => Self::vec_to_state(s.as_str(), v),
+                        _ => {
+                            self.error = Some(format!("No registers match {}", s));
+                            TState::Ambiguous(prefix.clone(), vec.clone())
+                        }
+                    }
+                }
+                Event::Number(x) => {
+                    self.error = Some(format!(
+                        "No candidate {}, enter a number from 0 to {}",
+                        x,
+                        vec.len() - 1
+                    ));
+                    TState::Ambiguous(prefix.clone(), vec.clone())
+                }
+                Event::Field(_) | Event::Expr(_) => {
+                    self.error = Some(String::from("No register selected"));
+                    TState::Ambiguous(prefix.clone(), vec.clone())
+                }
+                /* Nothing to record for undo */
+                Event::Empty => return,
             },
 
             /* From Selected */
@@ -79,6 +164,29 @@ impl<T: Clone + Item, F: Fn(&str) -> Vec<T>> Fsm<T, F> {
                 TState::Selected(r)
             }
 
+            (TState::Selected(reg), Event::Field(op)) => {
+                let mut r = reg.clone();
+                match r.apply(&op) {
+                    Ok(()) => TState::Selected(r),
+                    Err(e) => {
+                        self.error = Some(e);
+                        TState::Selected(reg.clone())
+                    }
+                }
+            }
+
+            (TState::Selected(reg), Event::Expr(expr)) => match reg.eval(&expr) {
+                Ok(x) => {
+                    let mut r = reg.clone();
+                    r.update(x);
+                    TState::Selected(r)
+                }
+                Err(e) => {
+                    self.error = Some(e);
+                    TState::Selected(reg.clone())
+                }
+            },
+
             (TState::Selected(_), Event::Text(s)) => {
                 let v = (self.from_prefix)(&s);
                 Self::vec_to_state(s.as_str(), v)
@@ -87,6 +195,44 @@ impl<T: Clone + Item, F: Fn(&str) -> Vec<T>> Fsm<T, F> {
             /* Final */
             (_, Event::Empty) => TState::Final,
             (TState::Final, _) => TState::Final,
+        };
+
+        /* Rejected events and exit don't go to the undo stack */
+        match state {
+            TState::Final => self.state = state,
+            _ if self.error.is_none() => self.set_state(state),
+            _ => (),
+        }
+    }
+
+    /// Switch to `state` recording the current one for undo
+    pub fn set_state(&mut self, state: TState<T>) {
+        let prev = std::mem::replace(&mut self.state, state);
+        if !matches!((&prev, &self.state), (TState::Empty, TState::Empty)) {
+            self.undo.push(prev);
+            self.redo.clear();
+        }
+    }
+
+    /// Return to the previous state, `false` if there is none
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(state) => {
+                self.redo.push(std::mem::replace(&mut self.state, state));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapply the last undone transition, `false` if there is none
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(state) => {
+                self.undo.push(std::mem::replace(&mut self.state, state));
+                true
+            }
+            None => false,
         }
     }
 
@@ -94,13 +240,17 @@ impl<T: Clone + Item, F: Fn(&str) -> Vec<T>> Fsm<T, F> {
         Fsm {
             state: TState::Empty,
             from_prefix: f,
+            error: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::prefix_fsm::{Event, Fsm, Item, TState};
+    use crate::prefix_fsm::{Event, FieldOp, Fsm, Item, TState};
+    use asl_parser::{expr::Expr, RegisterDesc};
     #[derive(Clone, PartialEq)]
     struct Elem(String, Option<u64>);
     impl Elem {
@@ -109,9 +259,30 @@ mod tests {
         }
     }
     impl Item for Elem {
+        fn name(&self) -> &str {
+            &self.0
+        }
         fn update(&mut self, x: u64) {
             self.1 = Some(x);
         }
+        fn apply(&mut self, op: &FieldOp) -> Result<(), String> {
+            match op {
+                FieldOp::Set(f) if f == "0" => {
+                    self.1 = Some(self.1.unwrap_or(0) | 1);
+                    Ok(())
+                }
+                _ => Err(format!("Unknown field: {}", op.field())),
+            }
+        }
+        fn eval(&self, expr: &Expr) -> Result<u64, String> {
+            let reg = RegisterDesc {
+                name: self.0.clone(),
+                bits: 64,
+                value: self.1,
+                ..Default::default()
+            };
+            expr.eval(Some(&reg))
+        }
     }
 
     fn make_text(s: &str) -> Event {
@@ -182,6 +353,13 @@ mod tests {
         fsm.next(Event::Number(1));
         assert_empty(&fsm.state);
 
+        /*
+         * Empty ---|Field|--> Empty
+         */
+        fsm.next(Event::Field(FieldOp::Set(String::from("0"))));
+        assert_empty(&fsm.state);
+        assert!(fsm.error.is_some());
+
         /*
          * Empty ---|Text(miss)|--> Empty
          */
@@ -206,6 +384,29 @@ mod tests {
         fsm.next(Event::Number(43));
         assert_selected_num(&fsm.state, "Single", 43);
 
+        /*
+         * Selected ---|Field|--> Selected
+         */
+        fsm.next(Event::Field(FieldOp::Set(String::from("0"))));
+        assert_selected_num(&fsm.state, "Single", 43);
+        assert!(fsm.error.is_none());
+        fsm.next(Event::Field(FieldOp::Clear(String::from("X"))));
+        assert_selected_num(&fsm.state, "Single", 43);
+        assert_eq!(fsm.error.as_deref(), Some("Unknown field: X"));
+
+        /*
+         * Selected ---|Expr|--> Selected
+         */
+        fsm.next(Event::from_str("$ + 2"));
+        assert_selected_num(&fsm.state, "Single", 45);
+        fsm.next(Event::from_str("($ | 0x100) - 1"));
+        assert_selected_num(&fsm.state, "Single", 300);
+        fsm.next(Event::from_str("$ & ~0x100 ^ 1"));
+        assert_selected_num(&fsm.state, "Single", 45);
+        fsm.next(Event::from_str("$.x"));
+        assert_selected_num(&fsm.state, "Single", 45);
+        assert!(fsm.error.is_some());
+
         /*
          * Selected ---|Text(match)|--> Selected
          */
@@ -225,22 +426,73 @@ mod tests {
         assert_ambiguous(&fsm.state, &data, "Mult");
 
         /*
-         * Ambiguous ---|Text|--> Ambiguous
+         * Ambiguous ---|Text(miss)|--> Ambiguous
          */
-        fsm.next(make_text("Single"));
+        fsm.next(make_text("None"));
         assert_ambiguous(&fsm.state, &data, "Mult");
+        assert!(fsm.error.is_some());
+
+        /*
+         * Ambiguous ---|Text(amb.)|--> Ambiguous
+         */
+        fsm.next(make_text("Multiple"));
+        assert_ambiguous(&fsm.state, &data, "Multiple");
 
         /*
          * Ambiguous ---|Number (not matching)|--> Ambiguous
          */
         fsm.next(Event::Number(2));
+        assert_ambiguous(&fsm.state, &data, "Multiple");
+        assert_eq!(
+            fsm.error.as_deref(),
+            Some("No candidate 2, enter a number from 0 to 1")
+        );
+        /* Rejected selection doesn't replace the undo history */
+        assert!(fsm.undo());
         assert_ambiguous(&fsm.state, &data, "Mult");
+        assert!(fsm.redo());
+        assert_ambiguous(&fsm.state, &data, "Multiple");
 
         /*
          * Ambiguous ---|Number|--> Selected
          */
         fsm.next(Event::Number(1));
         assert_selected(&fsm.state, "Multiple2");
+
+        /*
+         * Ambiguous ---|Text(match)|--> Selected
+         */
+        fsm.next(make_text("Mult"));
+        fsm.next(make_text("Multiple1"));
+        assert_selected(&fsm.state, "Multiple1");
+    }
+
+    #[test]
+    fn test_refine() {
+        let data = vec!["SCTLR_EL1", "SCTLR_EL2", "ACTLR_EL1", "ACTLR_EL2"];
+        /* Substring search like the interactive mode */
+        let mut fsm = Fsm::new(|s| -> Vec<Elem> {
+            data.iter()
+                .filter(|x| x.contains(s))
+                .map(|x| Elem::new(x))
+                .collect()
+        });
+
+        fsm.next(make_text("SCTLR"));
+        assert_ambiguous(&fsm.state, &data, "SCTLR");
+
+        /* EL1 also matches ACTLR_EL1, which is not a candidate */
+        fsm.next(make_text("EL1"));
+        assert_selected(&fsm.state, "SCTLR_EL1");
+
+        /* Text matching no candidate starts a new search */
+        fsm.next(make_text("SCTLR"));
+        fsm.next(make_text("ACTLR"));
+        assert!(fsm.error.is_none());
+        assert!(matches!(&fsm.state, TState::Ambiguous(s, v) if s == "ACTLR" && v.len() == 2));
+
+        fsm.next(make_text("XYZ"));
+        assert_eq!(fsm.error.as_deref(), Some("No registers match XYZ"));
     }
 
     #[test]
@@ -250,5 +502,68 @@ mod tests {
 
         let e = Event::from_str("0x1");
         assert_eq!(e, Event::Number(1));
+
+        let e = Event::from_str("0b101");
+        assert_eq!(e, Event::Number(5));
+
+        let e = Event::from_str("TG0 = 0b10");
+        let op = FieldOp::Assign(String::from("tg0"), String::from("0b10"));
+        assert_eq!(e, Event::Field(op));
+
+        let e = Event::from_str("toggle PAN");
+        assert_eq!(e, Event::Field(FieldOp::Toggle(String::from("pan"))));
+
+        let e = Event::from_str("1 << 12 | 0x5");
+        assert_eq!(e, Event::Number(0x1005));
+
+        let e = Event::from_str("0o1_7");
+        assert_eq!(e, Event::Number(15));
+
+        let e = Event::from_str("$ | (1 << 12)");
+        assert!(matches!(e, Event::Expr(_)));
+
+        let e = Event::from_str("sctlr_el1");
+        assert_eq!(e, Event::Text(String::from("sctlr_el1")));
+
+        let e = Event::from_str("clear 23");
+        assert_eq!(e, Event::Field(FieldOp::Clear(String::from("23"))));
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut fsm = Fsm::new(|prefix| -> Vec<Elem> {
+            ["Single", "Other"]
+                .iter()
+                .filter(|x| x.starts_with(prefix))
+                .map(|x| Elem::new(x))
+                .collect()
+        });
+        assert!(!fsm.undo());
+
+        fsm.next(make_text("Si"));
+        fsm.next(Event::Number(1));
+        fsm.next(Event::Number(2));
+        /* Rejected event is not recorded */
+        fsm.next(Event::Field(FieldOp::Clear(String::from("X"))));
+        assert_selected_num(&fsm.state, "Single", 2);
+
+        assert!(fsm.undo());
+        assert_selected_num(&fsm.state, "Single", 1);
+        assert!(fsm.undo());
+        assert_selected(&fsm.state, "Single");
+        assert!(fsm.redo());
+        assert_selected_num(&fsm.state, "Single", 1);
+
+        /* New transition drops undone states */
+        fsm.next(make_text("Ot"));
+        assert!(!fsm.redo());
+        assert_selected(&fsm.state, "Other");
+        assert!(fsm.undo());
+        assert_selected_num(&fsm.state, "Single", 1);
+
+        assert!(fsm.undo());
+        assert!(fsm.undo());
+        assert_empty(&fsm.state);
+        assert!(!fsm.undo());
     }
 }
@@ -0,0 +1,801 @@
+use std::{
+    cmp::max,
+    collections::BTreeMap,
+    fmt::{self, Write},
+    str::FromStr,
+};
+
+use crate::{features::FeatureSet, BitfieldDesc, RegisterDesc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// Pick the widest layout which fits into the available width
+    Auto,
+    /// Single table row for the whole register
+    Table,
+    /// Stacked tables with given number of bits each
+    Chunked(u32),
+    /// One field per line
+    Vertical,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Layout::Auto),
+            "table" => Ok(Layout::Table),
+            "chunked" | "chunked32" => Ok(Layout::Chunked(32)),
+            "chunked16" => Ok(Layout::Chunked(16)),
+            "vertical" => Ok(Layout::Vertical),
+            _ => Err(format!(
+                "Unknown layout {}, expected auto, table, chunked32, chunked16 or vertical",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Radix {
+    Dec,
+    Hex,
+    /// Binary padded to the field width
+    Bin,
+    /// Decimal for single bits, binary up to 4 bits, hex for wider fields
+    Auto,
+}
+
+impl FromStr for Radix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dec" => Ok(Radix::Dec),
+            "hex" => Ok(Radix::Hex),
+            "bin" => Ok(Radix::Bin),
+            "auto" => Ok(Radix::Auto),
+            _ => Err(format!(
+                "Unknown radix {}, expected dec, hex, bin or auto",
+                s
+            )),
+        }
+    }
+}
+
+impl Radix {
+    /// Format `value` of a field `width` bits wide
+    pub fn format(&self, value: u64, width: u32) -> String {
+        let width = width as usize;
+        match self {
+            Radix::Dec => format!("{}", value),
+            Radix::Hex => format!("0x{:x}", value),
+            Radix::Bin => format!("0b{:0width$b}", value),
+            Radix::Auto => match width {
+                1 => Radix::Dec.format(value, width as u32),
+                2..=4 => Radix::Bin.format(value, width as u32),
+                _ => Radix::Hex.format(value, width as u32),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Ascii,
+    /// Unicode box-drawing characters
+    Unicode,
+}
+
+struct Frame {
+    top: [char; 3],
+    middle: [char; 3],
+    bottom: [char; 3],
+    horizontal: char,
+    vertical: char,
+}
+
+const ASCII_FRAME: Frame = Frame {
+    top: ['+', '+', '+'],
+    middle: ['+', '+', '+'],
+    bottom: ['+', '+', '+'],
+    horizontal: '-',
+    vertical: '|',
+};
+
+const UNICODE_FRAME: Frame = Frame {
+    top: ['┌', '┬', '┐'],
+    middle: ['├', '┼', '┤'],
+    bottom: ['└', '┴', '┘'],
+    horizontal: '─',
+    vertical: '│',
+};
+
+impl Charset {
+    fn frame(&self) -> &'static Frame {
+        match self {
+            Charset::Ascii => &ASCII_FRAME,
+            Charset::Unicode => &UNICODE_FRAME,
+        }
+    }
+}
+
+const DIM: &str = "\x1b[2m";
+const HIGHLIGHT: &str = "\x1b[1;32m";
+const CHANGED: &str = "\x1b[1;33m";
+const ERROR: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// Marker appended to values which differ from `RenderOptions::previous`
+const CHANGED_MARKER: char = '*';
+/// Marker appended to values contradicting RES0, RES1 or RAZ/WI attribute
+const VIOLATION_MARKER: char = '!';
+/// Marker appended to names of fields requiring features missing from
+/// `RenderOptions::features`
+const ABSENT_MARKER: char = '?';
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub layout: Layout,
+    /// Available width in columns, `None` if unlimited
+    pub width: Option<usize>,
+    pub charset: Charset,
+    /// Use ANSI escape sequences to highlight values
+    pub color: bool,
+    /// Value to compare against, changed fields are marked
+    pub previous: Option<u64>,
+    /// Default radix for field values
+    pub radix: Radix,
+    /// Radix overrides by lowercase field name
+    pub field_radix: BTreeMap<String, Radix>,
+    /// Features of the target, fields requiring other ones are marked
+    pub features: Option<FeatureSet>,
+    /// Show fields requiring missing features as padding instead of marking
+    pub hide_absent: bool,
+}
+
+impl RenderOptions {
+    fn radix_for(&self, field: &BitfieldDesc) -> Radix {
+        field
+            .name
+            .as_ref()
+            .and_then(|x| self.field_radix.get(&x.to_lowercase()))
+            .copied()
+            .unwrap_or(self.radix)
+    }
+
+    fn is_absent(&self, field: &BitfieldDesc) -> bool {
+        self.features
+            .as_ref()
+            .is_some_and(|x| !x.has_all(&field.features))
+    }
+
+    /// Field name with a marker if it requires missing features
+    fn label(&self, field: &BitfieldDesc) -> String {
+        let mut label = field.label();
+        if self.is_absent(field) {
+            label.push(ABSENT_MARKER);
+        }
+        label
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            layout: Layout::Table,
+            width: None,
+            charset: Charset::Ascii,
+            color: false,
+            previous: None,
+            radix: Radix::Dec,
+            field_radix: BTreeMap::new(),
+            features: None,
+            hide_absent: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Paint {
+    Plain,
+    Dim,
+    Highlight,
+    Changed,
+    Error,
+}
+
+impl Paint {
+    fn code(&self) -> Option<&'static str> {
+        match self {
+            Paint::Plain => None,
+            Paint::Dim => Some(DIM),
+            Paint::Highlight => Some(HIGHLIGHT),
+            Paint::Changed => Some(CHANGED),
+            Paint::Error => Some(ERROR),
+        }
+    }
+}
+
+fn value_cell(field: &BitfieldDesc, value: u64, opts: &RenderOptions) -> (String, Paint) {
+    let x = field.extract(value);
+    let changed = opts.previous.is_some_and(|p| field.extract(p) != x);
+    let text = opts.radix_for(field).format(x, field.width());
+    let text = match field.meaning(x) {
+        Some(meaning) => format!("{} ({})", text, meaning),
+        None => text,
+    };
+
+    let violated = field.is_violated(value);
+
+    let paint = if violated {
+        Paint::Error
+    } else if changed {
+        Paint::Changed
+    } else if field.is_padding() {
+        Paint::Dim
+    } else if x != 0 {
+        Paint::Highlight
+    } else {
+        Paint::Plain
+    };
+
+    let mut text = format!(" {}", text);
+    if changed {
+        text.push(CHANGED_MARKER);
+    }
+    if violated {
+        text.push(VIOLATION_MARKER);
+    }
+    text.push(' ');
+
+    (text, paint)
+}
+
+struct Cells {
+    names: Vec<String>,
+    ranges: Vec<String>,
+    values: Vec<(String, Paint)>,
+    padding: Vec<bool>,
+}
+
+impl Cells {
+    fn new(fields: &[BitfieldDesc], value: Option<u64>, opts: &RenderOptions) -> Self {
+        let mut names = Vec::new();
+        let mut ranges = Vec::new();
+        let mut values = Vec::new();
+        let mut padding = Vec::new();
+
+        for field in fields {
+            names.push(format! {" {} ", opts.label(field)});
+            ranges.push(format!(" {} ", field.bits_label()));
+            padding.push(field.is_padding() || opts.is_absent(field));
+
+            match value {
+                Some(x) => values.push(value_cell(field, x, opts)),
+                None => values.push((String::new(), Paint::Plain)),
+            }
+        }
+
+        Cells {
+            names,
+            ranges,
+            values,
+            padding,
+        }
+    }
+
+    fn sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.names
+            .iter()
+            .zip(&self.ranges)
+            .zip(&self.values)
+            .map(|((a, b), c)| max(max(a.len(), b.len()), c.0.chars().count()))
+    }
+
+    fn width(&self) -> usize {
+        self.sizes().map(|x| x + 1).sum::<usize>() + 1
+    }
+}
+
+/// Write fields as a table with bit positions, names and, if present,
+/// values rows
+pub fn write_table(
+    f: &mut dyn fmt::Write,
+    fields: &[BitfieldDesc],
+    value: Option<u64>,
+    opts: &RenderOptions,
+) -> fmt::Result {
+    let cells = Cells::new(fields, value, opts);
+    let frame = opts.charset.frame();
+    enum Row {
+        Names,
+        Bits,
+        Values,
+    }
+
+    let print_row = |f: &mut dyn fmt::Write, index: Row| -> fmt::Result {
+        for (i, size) in cells.sizes().enumerate() {
+            let (d, paint) = match &index {
+                Row::Names if cells.padding[i] => (&cells.names[i], Paint::Dim),
+                Row::Names => (&cells.names[i], Paint::Plain),
+                Row::Bits => (&cells.ranges[i], Paint::Plain),
+                Row::Values => (&cells.values[i].0, cells.values[i].1),
+            };
+            let len = d.chars().count();
+
+            let offset = (size - len) / 2;
+            write!(f, "{}", frame.vertical)?;
+            for _ in 0..offset {
+                write!(f, " ")?;
+            }
+            match paint.code().filter(|_| opts.color) {
+                Some(code) => write!(f, "{}{}{}", code, d, RESET)?,
+                None => write!(f, "{}", d)?,
+            }
+
+            for _ in offset + len..size {
+                write!(f, " ")?;
+            }
+        }
+
+        writeln!(f, "{}", frame.vertical)
+    };
+
+    let print_line = |f: &mut dyn fmt::Write, corners: &[char; 3]| -> fmt::Result {
+        for (i, size) in cells.sizes().enumerate() {
+            write!(f, "{}", if i == 0 { corners[0] } else { corners[1] })?;
+            for _ in 0..size {
+                write!(f, "{}", frame.horizontal)?;
+            }
+        }
+        writeln!(f, "{}", corners[2])
+    };
+
+    print_line(f, &frame.top)?;
+    print_row(f, Row::Bits)?;
+    print_line(f, &frame.middle)?;
+    print_row(f, Row::Names)?;
+    if value.is_some() {
+        print_line(f, &frame.middle)?;
+        print_row(f, Row::Values)?;
+    }
+    print_line(f, &frame.bottom)
+}
+
+fn write_chunks(
+    f: &mut dyn fmt::Write,
+    reg: &RegisterDesc,
+    bits: u32,
+    opts: &RenderOptions,
+) -> fmt::Result {
+    let lanes = reg.split_lanes(bits);
+
+    for (i, lane) in lanes.iter().rev().enumerate() {
+        if i != 0 {
+            writeln!(f)?;
+        }
+        write_table(f, lane, reg.value, opts)?;
+    }
+    Ok(())
+}
+
+fn write_vertical(f: &mut dyn fmt::Write, reg: &RegisterDesc, opts: &RenderOptions) -> fmt::Result {
+    let values: Vec<_> = reg
+        .fields
+        .iter()
+        .map(|field| match reg.value {
+            Some(x) => {
+                let (text, paint) = value_cell(field, x, opts);
+                (text.trim().to_string(), paint)
+            }
+            None => (String::new(), Paint::Plain),
+        })
+        .collect();
+
+    let bits_width = reg.fields.iter().map(|x| x.bits_label().len()).max();
+    let name_width = reg.fields.iter().map(|x| opts.label(x).len()).max();
+    let value_width = values.iter().map(|x| x.0.chars().count()).max();
+
+    for (field, (value, paint)) in reg.fields.iter().zip(&values) {
+        let mut line = format!(
+            "{:>bits_width$}  {:name_width$}",
+            field.bits_label(),
+            opts.label(field),
+            bits_width = bits_width.unwrap_or(0),
+            name_width = name_width.unwrap_or(0),
+        );
+
+        if reg.value.is_some() {
+            let pad = value_width.unwrap_or(0) - value.chars().count();
+            match paint.code().filter(|_| opts.color) {
+                Some(code) => write!(line, "  {}{}{}", code, value, RESET)?,
+                None => write!(line, "  {}", value)?,
+            }
+            line.extend(std::iter::repeat_n(' ', pad));
+        }
+
+        if let Some(description) = &field.description {
+            write!(line, "  {}", description)?;
+        }
+        writeln!(f, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+fn fits(reg: &RegisterDesc, bits: u32, width: usize, opts: &RenderOptions) -> bool {
+    reg.split_lanes(bits)
+        .iter()
+        .all(|lane| Cells::new(lane, reg.value, opts).width() <= width)
+}
+
+/// Resolve `Layout::Auto` to the widest layout fitting into `width`
+pub fn pick_layout(reg: &RegisterDesc, opts: &RenderOptions) -> Layout {
+    let width = match (opts.layout, opts.width) {
+        (Layout::Auto, Some(width)) => width,
+        (Layout::Auto, None) => return Layout::Table,
+        (layout, _) => return layout,
+    };
+
+    if fits(reg, reg.bits, width, opts) {
+        Layout::Table
+    } else if fits(reg, 32, width, opts) {
+        Layout::Chunked(32)
+    } else if fits(reg, 16, width, opts) {
+        Layout::Chunked(16)
+    } else {
+        Layout::Vertical
+    }
+}
+
+/// Whole register value in hex and in binary with `_` between fields,
+/// e.g. `0x123 = 0b0_0_..._10_0011`
+pub fn value_line(reg: &RegisterDesc, value: u64) -> String {
+    let digits = reg.bits.div_ceil(4) as usize;
+    let bin: Vec<_> = reg
+        .fields
+        .iter()
+        .map(|x| format!("{:0width$b}", x.extract(value), width = x.width() as usize))
+        .collect();
+
+    format!("0x{:0digits$x} = 0b{}", value, bin.join("_"))
+}
+
+fn write_register(f: &mut dyn fmt::Write, reg: &RegisterDesc, opts: &RenderOptions) -> fmt::Result {
+    write!(f, "{}", reg.name)?;
+    if let Some(encoding) = reg.encoding {
+        write!(f, " ({})", encoding)?;
+    }
+    if let Some(variant) = reg.value.and_then(|x| reg.variant(x)) {
+        write!(f, ": {}", variant.name)?;
+    }
+    writeln!(f)?;
+    if let Some(profile) = &opts.features {
+        let missing: Vec<_> = profile.missing(&reg.features).map(String::as_str).collect();
+        if !missing.is_empty() {
+            writeln!(
+                f,
+                "Warning: requires {} missing from the profile",
+                missing.join(", ")
+            )?;
+        }
+    }
+    if let Some(value) = reg.value {
+        writeln!(f, "{}", value_line(reg, value))?;
+    }
+
+    match pick_layout(reg, opts) {
+        Layout::Auto | Layout::Table => write_table(f, &reg.fields, reg.value, opts)?,
+        Layout::Chunked(bits) => write_chunks(f, reg, bits, opts)?,
+        Layout::Vertical => write_vertical(f, reg, opts)?,
+    }
+
+    if let Some(profile) = &opts.features {
+        for field in reg.fields.iter().filter(|x| opts.is_absent(x)) {
+            let missing: Vec<_> = profile
+                .missing(&field.features)
+                .map(String::as_str)
+                .collect();
+            writeln!(
+                f,
+                "{}{} requires {}",
+                field.label(),
+                ABSENT_MARKER,
+                missing.join(", ")
+            )?;
+        }
+    }
+
+    if let Some(value) = reg.value {
+        for field in reg.fields.iter().filter(|x| x.is_violated(value)) {
+            let (bits, verb, have) = if field.width() == 1 {
+                ("bit", "is", "has")
+            } else {
+                ("bits", "are", "have")
+            };
+            writeln!(
+                f,
+                "Warning: {} {} {} {} but {} value {:#x}",
+                bits,
+                field.bits_label(),
+                verb,
+                field.attr.map(|x| x.to_string()).unwrap_or_default(),
+                have,
+                field.extract(value)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_diff(
+    f: &mut dyn fmt::Write,
+    reg: &RegisterDesc,
+    old: u64,
+    new: u64,
+    opts: &RenderOptions,
+) -> fmt::Result {
+    let digits = reg.bits.div_ceil(4) as usize;
+    writeln!(f, "{}", reg.name)?;
+    writeln!(f, "- 0x{:0digits$x}", old)?;
+    writeln!(f, "+ 0x{:0digits$x}", new)?;
+
+    let diff = reg.diff(old, new);
+    if diff.is_empty() {
+        return writeln!(f, "No differences");
+    }
+
+    let format = |field: &BitfieldDesc, x: u64| {
+        let text = opts.radix_for(field).format(x, field.width());
+        match field.meaning(x) {
+            Some(meaning) => format!("{} ({})", text, meaning),
+            None => text,
+        }
+    };
+    let rows: Vec<_> = diff
+        .iter()
+        .map(|x| {
+            (
+                x.field.bits_label(),
+                opts.label(&x.field),
+                format(&x.field, x.old),
+                format(&x.field, x.new),
+            )
+        })
+        .collect();
+
+    let bits_width = rows.iter().map(|x| x.0.len()).max().unwrap_or(0);
+    let name_width = rows.iter().map(|x| x.1.len()).max().unwrap_or(0);
+    let old_width = rows.iter().map(|x| x.2.chars().count()).max().unwrap_or(0);
+
+    for (bits, name, old, new) in rows {
+        let pad = old_width - old.chars().count();
+        let new = match Paint::Changed.code().filter(|_| opts.color) {
+            Some(code) => format!("{}{}{}", code, new, RESET),
+            None => new,
+        };
+        writeln!(
+            f,
+            "{:>bits_width$}  {:name_width$}  {}{}  ->  {}",
+            bits,
+            name,
+            old,
+            " ".repeat(pad),
+            new
+        )?;
+    }
+    Ok(())
+}
+
+/// Fields differing between `old` and `new` values of the register with
+/// both values side by side
+pub fn render_diff(reg: &RegisterDesc, old: u64, new: u64, opts: &RenderOptions) -> String {
+    let reg = match &opts.features {
+        Some(profile) if opts.hide_absent => reg.with_features(profile),
+        _ => reg.clone(),
+    };
+    let mut out = String::new();
+    write_diff(&mut out, &reg, old, new, opts).expect("Writing to String can't fail");
+    out
+}
+
+/// Render register with its name and value as a header using given options
+pub fn render(reg: &RegisterDesc, opts: &RenderOptions) -> String {
+    let mut out = String::new();
+    let reg = match &opts.features {
+        Some(profile) if opts.hide_absent => reg.resolve().with_features(profile),
+        _ => reg.resolve(),
+    };
+    write_register(&mut out, &reg, opts).expect("Writing to String can't fail");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        meta::apply_meta,
+        parse_registers,
+        render::{
+            pick_layout, render, render_diff, value_line, Charset, Layout, Radix, RenderOptions,
+        },
+    };
+
+    #[test]
+    fn test_layouts() {
+        let mut data = parse_registers("__register 64 { 63:63 HI, 0:0 LO } REG;");
+        let reg = data.get_mut("reg").unwrap();
+        reg.value = Some(1 << 63 | 1);
+
+        let out = render(reg, &RenderOptions::default());
+        let (header, table) = out.split_once('\n').unwrap();
+        let (value, table) = table.split_once('\n').unwrap();
+        assert_eq!(format!("{}\n{}", header, table), format!("{}", reg));
+        assert!(value.starts_with("0x8000000000000001 = 0b1_0000"));
+        assert!(value.ends_with("0000_1"));
+
+        let layout = |layout, width| {
+            let opts = RenderOptions {
+                layout,
+                width,
+                ..Default::default()
+            };
+            pick_layout(reg, &opts)
+        };
+
+        assert_eq!(layout(Layout::Auto, None), Layout::Table);
+        assert_eq!(layout(Layout::Auto, Some(19)), Layout::Table);
+        assert_eq!(layout(Layout::Auto, Some(18)), Layout::Chunked(32));
+        assert_eq!(layout(Layout::Auto, Some(12)), Layout::Vertical);
+        assert_eq!(layout(Layout::Vertical, Some(80)), Layout::Vertical);
+
+        let opts = RenderOptions {
+            layout: Layout::Chunked(32),
+            ..Default::default()
+        };
+        let out = render(reg, &opts);
+        assert!(out.contains("| 63 | 62..32 |"));
+        assert!(out.contains("| 31..1 | 0  |"));
+
+        let opts = RenderOptions {
+            layout: Layout::Vertical,
+            ..Default::default()
+        };
+        assert!(render(reg, &opts).ends_with("\n   63  HI  1\n62..1      0\n    0  LO  1\n"));
+    }
+
+    #[test]
+    fn test_styles() {
+        let mut data = parse_registers("__register 8 { 7:4 HI, 1:0 LO } REG;");
+        let reg = data.get_mut("reg").unwrap();
+        reg.value = Some(0x12);
+
+        let opts = RenderOptions {
+            charset: Charset::Unicode,
+            previous: Some(0x13),
+            ..Default::default()
+        };
+        let out = render(reg, &opts);
+        assert!(out.contains("\n┌──────┬──────┬──────┐\n"));
+        assert!(out.contains("│  1   │  0   │  2*  │"));
+        assert!(out.ends_with("└──────┴──────┴──────┘\n"));
+
+        let opts = RenderOptions {
+            color: true,
+            ..Default::default()
+        };
+        let out = render(reg, &opts);
+        assert!(out.contains("\x1b[2m  \x1b[0m"));
+        assert!(out.contains("\x1b[1;32m 1 \x1b[0m"));
+        assert!(out.contains("| \x1b[2m 0 \x1b[0m"));
+    }
+
+    #[test]
+    fn test_radix() {
+        assert_eq!(Radix::Dec.format(10, 4), "10");
+        assert_eq!(Radix::Hex.format(10, 4), "0xa");
+        assert_eq!(Radix::Bin.format(2, 4), "0b0010");
+        assert_eq!(Radix::Auto.format(1, 1), "1");
+        assert_eq!(Radix::Auto.format(3, 2), "0b11");
+        assert_eq!(Radix::Auto.format(255, 8), "0xff");
+
+        let mut data = parse_registers("__register 16 { 15:8 HI, 1:0 LO } REG;");
+        let reg = data.get_mut("reg").unwrap();
+        reg.value = Some(0x1203);
+
+        assert_eq!(value_line(reg, 0x1203), "0x1203 = 0b00010010_000000_11");
+
+        let opts = RenderOptions {
+            radix: Radix::Hex,
+            field_radix: [("lo".to_string(), Radix::Bin)].into(),
+            ..Default::default()
+        };
+        let out = render(reg, &opts);
+        assert!(out.contains("| 0x12  | 0x0  | 0b11 |"));
+    }
+
+    #[test]
+    fn test_meaning() {
+        let mut data = parse_registers("__register 8 { 3:0 M } REG;");
+        apply_meta(&mut data, "__values REG.M { 0b0101 = \"EL1h\" };").unwrap();
+        let reg = data.get_mut("reg").unwrap();
+
+        reg.value = Some(5);
+        assert!(render(reg, &Default::default()).contains("| 5 (EL1h) |"));
+
+        reg.value = Some(6);
+        assert!(!render(reg, &Default::default()).contains('('));
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut data = parse_registers("__register 8 { 7:4 HI } REG;");
+        apply_meta(&mut data, "__attrs REG { 3:2 RES0, 1:0 RES1 };").unwrap();
+        let reg = data.get_mut("reg").unwrap();
+
+        reg.value = Some(0x03);
+        let out = render(reg, &Default::default());
+        assert!(out.contains("| RES0 | RES1 |"));
+        assert!(!out.contains("Warning"));
+
+        reg.value = Some(0x04);
+        let out = render(reg, &Default::default());
+        assert!(out.contains("|  1!  |  0!  |"));
+        assert!(out.contains("Warning: bits 3..2 are RES0 but have value 0x1\n"));
+        assert!(out.contains("Warning: bits 1..0 are RES1 but have value 0x0\n"));
+    }
+
+    #[test]
+    fn test_features() {
+        let mut data = parse_registers("__register 32 { 22:22 PAN, 12:12 SSBS, 3:0 M } CPSR;");
+        let meta = "
+            __features CPSR.PAN { FEAT_PAN };
+            __features CPSR.SSBS { FEAT_SSBS };
+            __features CPSR { FEAT_AA32 };
+        ";
+        apply_meta(&mut data, meta).unwrap();
+        let reg = &data["cpsr"];
+
+        let mut opts = RenderOptions {
+            features: Some("armv8.2".parse().unwrap()),
+            ..Default::default()
+        };
+        let out = render(reg, &opts);
+        assert!(out.contains("Warning: requires FEAT_AA32 missing from the profile\n"));
+        assert!(out.contains("| PAN | "));
+        assert!(out.contains("| SSBS? |"));
+        assert!(out.contains("SSBS? requires FEAT_SSBS\n"));
+
+        opts.hide_absent = true;
+        let out = render(reg, &opts);
+        assert!(out.contains("| PAN | "));
+        assert!(!out.contains("SSBS"));
+
+        assert!(!render(reg, &Default::default()).contains('?'));
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut data = parse_registers("__register 32 { 31:26 EC, 25:25 IL, 24:0 ISS } ESR;");
+        let meta = "
+            __variant ESR.ISS when EC = 0x24 \"Data Abort\" { 6:6 WnR, 5:0 DFSC };
+            __values ESR.WnR { 0 = \"Read\", 1 = \"Write\" };
+        ";
+        apply_meta(&mut data, meta).unwrap();
+        let reg = &data["esr"];
+
+        let out = render_diff(reg, 0x92000005, 0x92000045, &Default::default());
+        assert_eq!(
+            out,
+            "ESR\n- 0x92000005\n+ 0x92000045\n6  WnR  0 (Read)  ->  1 (Write)\n"
+        );
+
+        /* Different variants are compared with the default layout */
+        let out = render_diff(reg, 0x92000005, 0x56000005, &Default::default());
+        assert!(out.contains("31..26  EC  36  ->  21\n"));
+        assert!(!out.contains("DFSC"));
+
+        let out = render_diff(reg, 1, 1, &Default::default());
+        assert!(out.ends_with("No differences\n"));
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt::Write;
+
+use serde_json::{json, Value};
+
+use crate::{search::FieldLocation, BitfieldDesc, RegisterDesc};
+
+/// Output format of scripting commands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+/// Named fields and reserved ranges, padding is left out
+fn reported_fields(reg: &RegisterDesc) -> Vec<BitfieldDesc> {
+    reg.resolve()
+        .fields
+        .into_iter()
+        .filter(|x| !x.label().is_empty())
+        .collect()
+}
+
+fn field_json(field: &BitfieldDesc, value: Option<u64>) -> Value {
+    let mut desc = json!({
+        "name": field.label(),
+        "from": field.from,
+        "to": field.to,
+    });
+
+    if let Some(description) = &field.description {
+        desc["description"] = json!(description);
+    }
+    if !field.values.is_empty() {
+        let values: Vec<_> = field
+            .values
+            .iter()
+            .map(|x| json!({ "value": x.value, "meaning": x.meaning }))
+            .collect();
+        desc["values"] = json!(values);
+    }
+    if !field.features.is_empty() {
+        desc["features"] = json!(field.features);
+    }
+    if let Some(value) = value {
+        let x = field.extract(value);
+        desc["value"] = json!(x);
+        if let Some(meaning) = field.meaning(x) {
+            desc["meaning"] = json!(meaning);
+        }
+    }
+    desc
+}
+
+/// Register layout with field values when the register has a value
+pub fn register_json(reg: &RegisterDesc) -> Value {
+    let mut desc = json!({
+        "name": reg.name,
+        "bits": reg.bits,
+    });
+
+    if let Some(encoding) = &reg.encoding {
+        desc["encoding"] = json!(encoding.to_string());
+    }
+    if let Some(value) = reg.value {
+        desc["value"] = json!(format!("{:#x}", value));
+        if let Some(variant) = reg.variant(value) {
+            desc["variant"] = json!(variant.name);
+        }
+    }
+    let fields: Vec<_> = reported_fields(reg)
+        .iter()
+        .map(|x| field_json(x, reg.value))
+        .collect();
+    desc["fields"] = json!(fields);
+    desc
+}
+
+/// Quote CSV cell if it holds a separator, quote or line break
+fn csv_cell(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Row per field of every register, value and meaning columns are empty
+/// for registers without a value
+pub fn registers_csv(regs: &[RegisterDesc]) -> String {
+    let mut out = String::from("register,field,bits,value,meaning\n");
+
+    for reg in regs {
+        for field in reported_fields(reg) {
+            let x = reg.value.map(|value| field.extract(value));
+            let cells = [
+                reg.name.clone(),
+                field.label(),
+                field.bits_label(),
+                x.map(|x| x.to_string()).unwrap_or_default(),
+                x.and_then(|x| field.meaning(x))
+                    .unwrap_or_default()
+                    .to_string(),
+            ];
+            let cells: Vec<_> = cells.iter().map(|x| csv_cell(x)).collect();
+            writeln!(out, "{}", cells.join(",")).expect("Writing to String can't fail");
+        }
+    }
+    out
+}
+
+/// Fields with their bits and descriptions, one per line
+pub fn fields_text(reg: &RegisterDesc) -> String {
+    let fields = reported_fields(reg);
+    let bits_width = fields
+        .iter()
+        .map(|x| x.bits_label().len())
+        .max()
+        .unwrap_or(0);
+    let name_width = fields.iter().map(|x| x.label().len()).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for field in fields {
+        let line = format!(
+            "{:>bits_width$}  {:<name_width$}  {}",
+            field.bits_label(),
+            field.label(),
+            field.description.as_deref().unwrap_or_default()
+        );
+        writeln!(out, "{}", line.trim_end()).expect("Writing to String can't fail");
+    }
+    out
+}
+
+/// Register names with their width and field count
+pub fn list(regs: &[&RegisterDesc], format: Format) -> String {
+    let fields = |reg: &RegisterDesc| reg.fields.iter().filter(|x| !x.is_padding()).count();
+
+    match format {
+        Format::Text => regs.iter().map(|x| format!("{}\n", x.name)).collect(),
+        Format::Json => {
+            let regs: Vec<_> = regs
+                .iter()
+                .map(|x| json!({ "name": x.name, "bits": x.bits, "fields": fields(x) }))
+                .collect();
+            serde_json::to_string_pretty(&regs).expect("Can't serialize register list") + "\n"
+        }
+        Format::Csv => {
+            let mut out = String::from("register,bits,fields\n");
+            for reg in regs {
+                writeln!(out, "{},{},{}", csv_cell(&reg.name), reg.bits, fields(reg))
+                    .expect("Writing to String can't fail");
+            }
+            out
+        }
+    }
+}
+
+/// Registers holding found fields with the field bits
+pub fn locations(found: &[&FieldLocation], format: Format) -> String {
+    match format {
+        Format::Text => {
+            let rows: Vec<_> = found
+                .iter()
+                .map(|x| {
+                    let mut field = x.field.label();
+                    if let Some(variant) = x.variant {
+                        write!(field, " ({})", variant).expect("Writing to String can't fail");
+                    }
+                    (x.register.name.as_str(), x.field.bits_label(), field)
+                })
+                .collect();
+            let reg_width = rows.iter().map(|x| x.0.len()).max().unwrap_or(0);
+            let bits_width = rows.iter().map(|x| x.1.len()).max().unwrap_or(0);
+
+            let mut out = String::new();
+            for (reg, bits, field) in rows {
+                writeln!(out, "{:<reg_width$}  {:>bits_width$}  {}", reg, bits, field)
+                    .expect("Writing to String can't fail");
+            }
+            out
+        }
+        Format::Json => {
+            let found: Vec<_> = found
+                .iter()
+                .map(|x| {
+                    json!({
+                        "register": x.register.name,
+                        "field": x.field.label(),
+                        "variant": x.variant,
+                        "from": x.field.from,
+                        "to": x.field.to,
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&found).expect("Can't serialize field list") + "\n"
+        }
+        Format::Csv => {
+            let mut out = String::from("register,field,variant,from,to\n");
+            for x in found {
+                writeln!(
+                    out,
+                    "{},{},{},{},{}",
+                    csv_cell(&x.register.name),
+                    csv_cell(&x.field.label()),
+                    csv_cell(x.variant.unwrap_or_default()),
+                    x.field.from,
+                    x.field.to
+                )
+                .expect("Writing to String can't fail");
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        meta::apply_meta,
+        parse_registers,
+        report::{fields_text, list, locations, register_json, registers_csv, Format},
+        search::FieldIndex,
+        RegisterDesc,
+    };
+
+    #[test]
+    fn test_report() {
+        let mut data = parse_registers(
+            "__register 64 { 63:3 RES0, 2:2 C, 0:0 M } SCTLR_EL1;
+             __register 32 { 0:0 M } SCTLR;",
+        );
+        apply_meta(
+            &mut data,
+            r#"__values SCTLR_EL1.M { 0 = "MMU disabled, off", 1 = "MMU enabled" };
+               __describe SCTLR_EL1.C "Cacheability control";"#,
+        )
+        .unwrap();
+        let reg = RegisterDesc {
+            value: Some(1),
+            ..data["sctlr_el1"].clone()
+        };
+
+        let json = register_json(&reg);
+        assert_eq!(json["value"], "0x1");
+        assert_eq!(json["fields"][1]["name"], "C");
+        assert_eq!(json["fields"][1]["description"], "Cacheability control");
+        assert_eq!(json["fields"][2]["meaning"], "MMU enabled");
+        assert!(register_json(&data["sctlr"])["fields"][0]
+            .get("value")
+            .is_none());
+
+        let csv = registers_csv(&[RegisterDesc {
+            value: Some(0),
+            ..reg.clone()
+        }]);
+        assert_eq!(
+            csv.lines().last(),
+            Some(r#"SCTLR_EL1,M,0,0,"MMU disabled, off""#)
+        );
+
+        assert_eq!(
+            fields_text(&reg),
+            "63..3  RES0\n    2  C     Cacheability control\n    0  M\n"
+        );
+        let regs: Vec<_> = data.values().collect();
+        assert_eq!(list(&regs, Format::Text), "SCTLR\nSCTLR_EL1\n");
+        assert_eq!(
+            list(&regs, Format::Csv),
+            "register,bits,fields\nSCTLR,32,1\nSCTLR_EL1,64,3\n"
+        );
+
+        let index = FieldIndex::new(&data);
+        let found = index.find("m");
+        assert_eq!(
+            locations(&found, Format::Text),
+            "SCTLR      0  M\nSCTLR_EL1  0  M\n"
+        );
+        assert_eq!(
+            locations(&found, Format::Csv),
+            "register,field,variant,from,to\nSCTLR,M,,0,0\nSCTLR_EL1,M,,0,0\n"
+        );
+    }
+}
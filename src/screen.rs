@@ -0,0 +1,375 @@
+use std::{collections::BTreeMap, io};
+
+use asl_parser::{render::RenderOptions, BitfieldDesc, RegisterDesc};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+/// Bits per row of the bit grid
+const ROW: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Focus {
+    List,
+    Bits,
+    Search,
+}
+
+struct App<'a> {
+    /// Registers matching the search, all of them when it's empty
+    matches: Vec<&'a RegisterDesc>,
+    all: Vec<&'a RegisterDesc>,
+    search: String,
+    list: ListState,
+    focus: Focus,
+    /// Values entered this session by register name
+    values: BTreeMap<String, u64>,
+    /// Bit under the cursor
+    cursor: u32,
+    quit: bool,
+}
+
+impl<'a> App<'a> {
+    fn new(all: Vec<&'a RegisterDesc>) -> Self {
+        let mut app = App {
+            matches: Vec::new(),
+            all,
+            search: String::new(),
+            list: ListState::default(),
+            focus: Focus::List,
+            values: BTreeMap::new(),
+            cursor: 0,
+            quit: false,
+        };
+        app.filter();
+        app
+    }
+
+    fn filter(&mut self) {
+        let search = self.search.to_lowercase();
+        self.matches = self
+            .all
+            .iter()
+            .filter(|x| x.name.to_lowercase().contains(&search))
+            .copied()
+            .collect();
+        self.list.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+        self.cursor = 0;
+    }
+
+    /// Selected register with its value, unset value reads as zero
+    fn selected(&self) -> Option<RegisterDesc> {
+        let reg = self.matches.get(self.list.selected()?)?;
+        Some(
+            RegisterDesc {
+                value: Some(self.values.get(&reg.name).copied().unwrap_or(0)),
+                ..(*reg).clone()
+            }
+            .resolve(),
+        )
+    }
+
+    fn select(&mut self, i: usize) {
+        if !self.matches.is_empty() {
+            self.list.select(Some(i.min(self.matches.len() - 1)));
+            self.cursor = 0;
+        }
+    }
+
+    fn toggle(&mut self) {
+        if let Some(reg) = self.selected() {
+            let value = reg.value.unwrap_or(0) ^ (1 << self.cursor);
+            self.values.insert(reg.name, value);
+        }
+    }
+
+    fn move_cursor(&mut self, delta: i64) {
+        let bits = self.selected().map_or(0, |x| grid_bits(&x)) as i64;
+        let cursor = self.cursor as i64 + delta;
+        if (0..bits).contains(&cursor) {
+            self.cursor = cursor as u32;
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        let i = self.list.selected().unwrap_or(0);
+        match (self.focus, key.code) {
+            (Focus::Search, KeyCode::Char(c)) => {
+                self.search.push(c);
+                self.filter();
+            }
+            (Focus::Search, KeyCode::Backspace) => {
+                self.search.pop();
+                self.filter();
+            }
+            (Focus::Search, KeyCode::Enter | KeyCode::Esc) => self.focus = Focus::List,
+            (Focus::Search, _) => (),
+            (_, KeyCode::Char('q') | KeyCode::Esc) => self.quit = true,
+            (_, KeyCode::Char('/')) => self.focus = Focus::Search,
+            (Focus::List, KeyCode::Tab | KeyCode::Enter) => self.focus = Focus::Bits,
+            (Focus::Bits, KeyCode::Tab) => self.focus = Focus::List,
+            (Focus::List, KeyCode::Up) => self.select(i.saturating_sub(1)),
+            (Focus::List, KeyCode::Down) => self.select(i + 1),
+            (Focus::List, KeyCode::PageUp) => self.select(i.saturating_sub(10)),
+            (Focus::List, KeyCode::PageDown) => self.select(i + 10),
+            (Focus::List, KeyCode::Home) => self.select(0),
+            (Focus::List, KeyCode::End) => self.select(usize::MAX),
+            /* Most significant bit is on the left */
+            (Focus::Bits, KeyCode::Left) => self.move_cursor(1),
+            (Focus::Bits, KeyCode::Right) => self.move_cursor(-1),
+            (Focus::Bits, KeyCode::Up) => self.move_cursor(ROW as i64),
+            (Focus::Bits, KeyCode::Down) => self.move_cursor(-(ROW as i64)),
+            (Focus::Bits, KeyCode::Char(' ')) => self.toggle(),
+            (Focus::Bits, KeyCode::Char('c')) => {
+                if let Some(reg) = self.selected() {
+                    self.values.remove(&reg.name);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [left, right] =
+            Layout::horizontal([Constraint::Length(32), Constraint::Min(0)]).areas(frame.area());
+        let [search, list] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(left);
+
+        let search_title = if self.focus == Focus::Search {
+            "Search (Enter to finish)"
+        } else {
+            "Search (/)"
+        };
+        frame.render_widget(
+            Paragraph::new(self.search.as_str())
+                .block(block(search_title, self.focus == Focus::Search)),
+            search,
+        );
+
+        let names: Vec<_> = self.matches.iter().map(|x| x.name.as_str()).collect();
+        let title = format!("Registers ({})", names.len());
+        frame.render_stateful_widget(
+            List::new(names)
+                .block(block(&title, self.focus == Focus::List))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut self.list,
+        );
+
+        match self.selected() {
+            Some(reg) => self.draw_register(frame, &reg, right),
+            None => frame.render_widget(
+                Paragraph::new("No registers match the search").block(block("", false)),
+                right,
+            ),
+        }
+    }
+
+    fn draw_register(&self, frame: &mut Frame, reg: &RegisterDesc, area: Rect) {
+        let bits = grid_bits(reg);
+        let rows = bits.div_ceil(ROW) as u16;
+        let [value, grid, details] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Length(rows * 2 + 2),
+            Constraint::Min(0),
+        ])
+        .areas(area);
+
+        let x = reg.value.unwrap_or(0);
+        let digits = bits.div_ceil(4) as usize;
+        let text = vec![
+            Line::from(format!("{:#0w$x}", x, w = digits + 2)),
+            Line::from(format!("{:#0w$b}", x, w = bits as usize + 2)),
+        ];
+        frame.render_widget(Paragraph::new(text).block(block(&reg.name, false)), value);
+
+        let field = field_at(reg, self.cursor);
+        let mut lines = Vec::new();
+        for row in (0..rows).rev() {
+            let bits: Vec<_> = (row as u32 * ROW..((row as u32 + 1) * ROW).min(bits))
+                .rev()
+                .collect();
+            lines.push(Line::from(
+                bits.iter()
+                    .map(|&b| Span::styled(format!("{:>3} ", b), Style::new().fg(Color::DarkGray)))
+                    .collect::<Vec<_>>(),
+            ));
+            lines.push(Line::from(
+                bits.iter()
+                    .map(|&b| {
+                        let mut style = Style::new();
+                        if field.as_ref().is_some_and(|f| (f.from..=f.to).contains(&b)) {
+                            style = style.fg(Color::Yellow);
+                        }
+                        if b == self.cursor && self.focus == Focus::Bits {
+                            style = style.add_modifier(Modifier::REVERSED);
+                        }
+                        Span::styled(format!("{:>3} ", (x >> b) & 1), style)
+                    })
+                    .collect::<Vec<_>>(),
+            ));
+        }
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                block("Bits", self.focus == Focus::Bits).title_bottom(
+                    " ←→↑↓ move  space toggle  c clear  tab switch  / search  q quit ",
+                ),
+            ),
+            grid,
+        );
+
+        let text = match &field {
+            Some(f) => field_details(f, x),
+            None => Vec::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .block(block("Field", false)),
+            details,
+        );
+    }
+}
+
+fn block(title: &str, focused: bool) -> Block<'static> {
+    let style = if focused {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new()
+    };
+    Block::bordered()
+        .title(title.to_string())
+        .border_style(style)
+}
+
+/// Bits shown in the grid, values are `u64` so bits from 64 up are left out
+fn grid_bits(reg: &RegisterDesc) -> u32 {
+    reg.bits.min(64)
+}
+
+/// Field of the resolved register covering `bit`
+fn field_at(reg: &RegisterDesc, bit: u32) -> Option<BitfieldDesc> {
+    reg.fields
+        .iter()
+        .find(|x| x.from <= bit && bit <= x.to)
+        .cloned()
+}
+
+fn field_details(field: &BitfieldDesc, value: u64) -> Vec<Line<'static>> {
+    let x = field.extract(value);
+    let mut lines = vec![
+        Line::from(format!("{} [{}]", field.label(), field.bits_label())),
+        Line::from(format!("Value: {} ({:#x})", x, x)),
+    ];
+    if let Some(meaning) = field.meaning(x) {
+        lines.push(Line::from(format!("Meaning: {}", meaning)));
+    }
+    if !field.features.is_empty() {
+        lines.push(Line::from(format!(
+            "Requires {}",
+            field.features.join(", ")
+        )));
+    }
+    if let Some(description) = &field.description {
+        lines.push(Line::from(""));
+        lines.push(Line::from(description.clone()));
+    }
+    lines
+}
+
+/// Full-screen mode with register list, bit grid and field details
+pub fn run_screen(data: &BTreeMap<String, RegisterDesc>, opts: &RenderOptions) -> io::Result<()> {
+    let profile = opts.features.as_ref().filter(|_| opts.hide_absent);
+    let regs = data
+        .values()
+        .filter(|reg| profile.is_none_or(|x| x.has_all(&reg.features)))
+        .collect();
+    let mut app = App::new(regs);
+
+    let mut terminal = ratatui::init();
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| app.draw(frame)) {
+            break Err(e);
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => app.handle_key(key),
+            Ok(_) => (),
+            Err(e) => break Err(e),
+        }
+        if app.quit {
+            break Ok(());
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use asl_parser::parse_registers;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+    use crate::screen::{App, Focus};
+
+    fn press(app: &mut App, keys: &[KeyCode]) {
+        for &key in keys {
+            app.handle_key(KeyEvent::from(key));
+        }
+    }
+
+    #[test]
+    fn test_keys() {
+        let data = parse_registers(
+            "__register 64 { 2:2 C, 0:0 M } SCTLR_EL1;
+             __register 64 { 15:14 TG0 } TCR_EL1;",
+        );
+        let mut app = App::new(data.values().collect());
+
+        press(
+            &mut app,
+            &[KeyCode::Char('/'), KeyCode::Char('T'), KeyCode::Char('c')],
+        );
+        press(&mut app, &[KeyCode::Char('r'), KeyCode::Enter]);
+        press(&mut app, &[KeyCode::Tab, KeyCode::Char(' ')]);
+        press(&mut app, &[KeyCode::Up, KeyCode::Left, KeyCode::Char(' ')]);
+        assert_eq!(app.matches.len(), 1);
+        assert_eq!(app.focus, Focus::Bits);
+        assert_eq!(app.selected().unwrap().value, Some(0x20001));
+
+        press(&mut app, &[KeyCode::Right, KeyCode::Char(' ')]);
+        assert_eq!(app.selected().unwrap().value, Some(0x30001));
+
+        press(&mut app, &[KeyCode::Char('c')]);
+        assert_eq!(app.selected().unwrap().value, Some(0));
+        press(&mut app, &[KeyCode::Char('q')]);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_wide_register() {
+        let data = parse_registers("__register 128 { 127:64 HI, 63:0 LO } REG128;");
+        let mut app = App::new(data.values().collect());
+
+        /* Cursor stops at bit 63 */
+        press(&mut app, &[KeyCode::Tab]);
+        press(&mut app, &[KeyCode::Up; 8]);
+        press(&mut app, &[KeyCode::Left; 20]);
+        assert_eq!(app.cursor, 63);
+        press(&mut app, &[KeyCode::Char(' ')]);
+        assert_eq!(app.selected().unwrap().value, Some(1 << 63));
+    }
+}
@@ -0,0 +1,263 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::{BitfieldDesc, RegisterDesc};
+
+/// Register search query, see [`Query::from_str`] for the syntax
+#[derive(Debug, Clone)]
+pub enum Query {
+    /// Prefix, substring or fuzzy match of register name
+    Name(String),
+    /// Registers having a field with this name
+    Field(String),
+    /// Glob or regular expression matching register name
+    Pattern(Regex),
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    /// `/regex/` is a regular expression, text with `*`, `?` or `[` is a glob
+    /// matching the whole name, `.FIELD` searches by field name and anything
+    /// else by register name. Matching is case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let pattern = |re: &str| {
+            RegexBuilder::new(re)
+                .case_insensitive(true)
+                .build()
+                .map(Query::Pattern)
+                .map_err(|e| e.to_string())
+        };
+
+        if let Some(re) = s
+            .strip_prefix('/')
+            .and_then(|x| x.strip_suffix('/'))
+            .filter(|x| !x.is_empty())
+        {
+            return pattern(re);
+        }
+        if s.contains(['*', '?', '[']) {
+            return pattern(&glob_to_regex(s));
+        }
+        if let Some(field) = s.strip_prefix('.') {
+            return Ok(Query::Field(field.to_string()));
+        }
+        Ok(Query::Name(s.to_string()))
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            '[' | ']' => re.push(c),
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+/// Gaps between characters of `pattern` found in order in `name`, `None`
+/// if `name` doesn't contain them all
+fn fuzzy_score(name: &str, pattern: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut chars = name.chars();
+    for p in pattern.chars() {
+        score += chars.by_ref().position(|c| c == p)?;
+    }
+    Some(score)
+}
+
+/// Registers matching `query`, best matches first. `index` must be built
+/// from `data`, it serves field queries.
+///
+/// Names starting with the query rank above names containing it, which rank
+/// above fuzzy matches, i.e. names containing query characters in order.
+/// Only the best non-empty group is returned, so a complete prefix doesn't
+/// drown in fuzzy matches.
+pub fn search<'a>(
+    data: &'a BTreeMap<String, RegisterDesc>,
+    index: &FieldIndex<'a>,
+    query: &Query,
+) -> Vec<&'a RegisterDesc> {
+    match query {
+        Query::Name(s) => {
+            let s = s.to_lowercase();
+            let prefix: Vec<_> = data
+                .range(s.clone()..)
+                .take_while(|x| x.0.starts_with(&s))
+                .map(|x| x.1)
+                .collect();
+            if !prefix.is_empty() {
+                return prefix;
+            }
+
+            let mut found: Vec<_> = data
+                .iter()
+                .filter_map(|(k, v)| k.find(&s).map(|i| (i, v)))
+                .collect();
+            if found.is_empty() {
+                found = data
+                    .iter()
+                    .filter_map(|(k, v)| fuzzy_score(k, &s).map(|i| (i, v)))
+                    .collect();
+            }
+            found.sort_by_key(|(score, reg)| (*score, reg.name.len()));
+            found.into_iter().map(|x| x.1).collect()
+        }
+        Query::Field(field) => {
+            let mut found: Vec<_> = index.get(field).iter().map(|x| x.register).collect();
+            /* Variants of a register may define the same field */
+            found.dedup_by(|a, b| a.name == b.name);
+            found
+        }
+        Query::Pattern(re) => data.values().filter(|reg| re.is_match(&reg.name)).collect(),
+    }
+}
+
+/// Field found in [`FieldIndex`]
+#[derive(Debug, Clone)]
+pub struct FieldLocation<'a> {
+    pub register: &'a RegisterDesc,
+    pub field: &'a BitfieldDesc,
+    /// Name of the layout variant defining the field
+    pub variant: Option<&'a str>,
+}
+
+/// Reverse index from field name to registers holding the field, including
+/// fields of layout variants. Build it after metadata is applied, it adds
+/// variant fields and descriptions.
+pub struct FieldIndex<'a> {
+    /// Locations by lowercase field name
+    fields: BTreeMap<String, Vec<FieldLocation<'a>>>,
+}
+
+impl<'a> FieldIndex<'a> {
+    pub fn new(data: &'a BTreeMap<String, RegisterDesc>) -> Self {
+        let mut fields: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for register in data.values() {
+            let variants = register
+                .variants
+                .iter()
+                .flat_map(|v| v.fields.iter().map(move |x| (x, Some(v.name.as_str()))));
+
+            for (field, variant) in register.fields.iter().map(|x| (x, None)).chain(variants) {
+                let Some(name) = field.name.as_ref().filter(|_| !field.is_padding()) else {
+                    continue;
+                };
+                fields
+                    .entry(name.to_lowercase())
+                    .or_default()
+                    .push(FieldLocation {
+                        register,
+                        field,
+                        variant,
+                    });
+            }
+        }
+        FieldIndex { fields }
+    }
+
+    /// Fields named `name`, case-insensitive
+    pub fn get(&self, name: &str) -> &[FieldLocation<'a>] {
+        self.fields
+            .get(&name.to_lowercase())
+            .map_or(&[], |x| x.as_slice())
+    }
+
+    /// Fields named `name` or, if there are none, fields mentioning it in
+    /// their description. Both are case-insensitive.
+    pub fn find(&self, name: &str) -> Vec<&FieldLocation<'a>> {
+        let found = self.get(name);
+        if !found.is_empty() {
+            return found.iter().collect();
+        }
+
+        let name = name.to_lowercase();
+        self.fields
+            .values()
+            .flatten()
+            .filter(|x| {
+                x.field
+                    .description
+                    .as_ref()
+                    .is_some_and(|d| d.to_lowercase().contains(&name))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        meta::apply_meta,
+        parse_registers,
+        search::{search, FieldIndex, Query},
+    };
+
+    #[test]
+    fn test_search() {
+        let data = parse_registers(
+            "__register 64 { 0:0 M } SCTLR_EL1;
+             __register 64 { 0:0 M } SCTLR_EL2;
+             __register 64 { 15:14 TG0 } TCR_EL1;
+             __register 64 { 15:12 TGran4 } ID_AA64MMFR0_EL1;
+             __register 64 { 3:0 HAFDBS } ID_AA64MMFR1_EL1;",
+        );
+        let index = FieldIndex::new(&data);
+        let names = |query: &str| -> Vec<String> {
+            search(&data, &index, &query.parse::<Query>().unwrap())
+                .into_iter()
+                .map(|x| x.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names("sctlr"), ["SCTLR_EL1", "SCTLR_EL2"]);
+        assert_eq!(names("mmfr1"), ["ID_AA64MMFR1_EL1"]);
+        assert_eq!(names("tcrel1"), ["TCR_EL1"]);
+        assert_eq!(names("mmfr0el1"), ["ID_AA64MMFR0_EL1"]);
+        assert_eq!(names(".tg0"), ["TCR_EL1"]);
+        assert_eq!(names("*_el2"), ["SCTLR_EL2"]);
+        assert_eq!(
+            names("/mmfr[01]/"),
+            ["ID_AA64MMFR0_EL1", "ID_AA64MMFR1_EL1"]
+        );
+        assert!(names("xyz").is_empty());
+        assert!("/(/".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn test_field_index() {
+        let mut data = parse_registers(
+            "__register 64 { 23:23 SPAN, 0:0 M } SCTLR_EL1;
+             __register 32 { 22:22 PAN } CPSR;
+             __register 64 { 31:26 EC, 24:0 ISS } ESR_EL1;",
+        );
+        apply_meta(
+            &mut data,
+            r#"__describe CPSR.PAN "Privileged Access Never";
+               __variant ESR_EL1.ISS when EC = 0x24 "Data Abort" { 6:6 WnR, 5:0 DFSC };"#,
+        )
+        .unwrap();
+        let index = FieldIndex::new(&data);
+
+        let found = index.find("pan");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].register.name, "CPSR");
+        assert_eq!(found[0].field.bits_label(), "22");
+
+        let found = index.find("WNR");
+        assert_eq!(found[0].register.name, "ESR_EL1");
+        assert_eq!(found[0].variant, Some("Data Abort"));
+
+        /* Description is searched when no field has the name */
+        let found = index.find("access never");
+        assert_eq!(found[0].field.name.as_deref(), Some("PAN"));
+        assert!(index.find("xyz").is_empty());
+    }
+}
@@ -1,34 +1,138 @@
-use std::{
-    collections::BTreeMap,
-    fmt,
-    io::{self, Write},
-};
+use std::{cell::RefCell, collections::BTreeMap, io};
 
-use crate::prefix_fsm::{Event, Fsm, Item, TState};
-use asl_parser::RegisterDesc;
+use crate::{
+    asl_helpers::history_path,
+    prefix_fsm::{Event, FieldOp, Fsm, Item, TState},
+};
+use asl_parser::{
+    encoding::{find_register, Encoding},
+    expr::Expr,
+    render::{render, render_diff, Radix, RenderOptions},
+    report,
+    search::{search, FieldIndex, Query},
+    RegisterDesc,
+};
+use rustyline::{
+    completion::Completer, error::ReadlineError, history::DefaultHistory, Context, Editor, Helper,
+    Highlighter, Hinter, Validator,
+};
+use terminal_size::{terminal_size, Height, Width};
 
+/// Register with its current and previous values
 #[derive(Clone)]
-struct Elem<'a>(&'a RegisterDesc, Option<u64>);
+struct Elem<'a>(&'a RegisterDesc, Option<u64>, Option<u64>);
 
 impl<'a> Item for Elem<'a> {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
     fn update(&mut self, x: u64) {
+        self.2 = self.1;
         self.1 = Some(x)
     }
-}
-impl<'a> fmt::Display for Elem<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            RegisterDesc {
-                name: self.0.name.clone(),
-                bits: self.0.bits,
-                fields: self.0.fields.clone(),
-                value: self.1,
+
+    fn apply(&mut self, op: &FieldOp) -> Result<(), String> {
+        let reg = RegisterDesc {
+            value: self.1,
+            ..self.0.clone()
+        };
+        let value = self.1.unwrap_or(0);
+
+        let new = match op {
+            FieldOp::Assign(field, s) => reg.assign(field, s)?,
+            _ => {
+                let field = reg
+                    .field(op.field())
+                    .ok_or_else(|| format!("Unknown field: {}", op.field()))?;
+                let ones = field.extract(u64::MAX);
+                let x = match op {
+                    FieldOp::Set(_) => ones,
+                    FieldOp::Clear(_) => 0,
+                    _ => field.extract(value) ^ ones,
+                };
+                field.insert(value, x)
             }
-        )?;
+        };
+
+        self.update(new);
         Ok(())
     }
+
+    fn eval(&self, expr: &Expr) -> Result<u64, String> {
+        let reg = RegisterDesc {
+            value: self.1,
+            ..self.0.clone()
+        };
+        expr.eval(Some(&reg))
+    }
+}
+
+impl<'a> Elem<'a> {
+    fn render(&self, opts: &RenderOptions) -> String {
+        let reg = RegisterDesc {
+            value: self.1,
+            ..self.0.clone()
+        };
+
+        /* Terminal may be resized between commands */
+        let opts = RenderOptions {
+            width: terminal_size().map(|(Width(w), _)| w as usize),
+            /* Keep plain output unchanged, changes are marked in fancy style only */
+            previous: self.2.filter(|_| opts.color),
+            ..opts.clone()
+        };
+
+        render(&reg, &opts)
+    }
+}
+
+/// Registers with lowercase names starting with `prefix`
+fn prefix_range<'a>(
+    data: &'a BTreeMap<String, RegisterDesc>,
+    prefix: &str,
+) -> impl Iterator<Item = &'a RegisterDesc> {
+    let prefix = String::from(prefix);
+    data.range(prefix.clone()..)
+        .take_while(move |x| x.0.starts_with(&prefix))
+        .map(|x| x.1)
+}
+
+/// Completion candidates for `word`: fields of the selected register first,
+/// then register names. Empty word completes fields only.
+fn complete(data: &BTreeMap<String, RegisterDesc>, fields: &[String], word: &str) -> Vec<String> {
+    let prefix = word.to_lowercase();
+    let mut candidates: Vec<_> = fields
+        .iter()
+        .filter(|x| x.to_lowercase().starts_with(&prefix))
+        .cloned()
+        .collect();
+
+    if !prefix.is_empty() {
+        candidates.extend(prefix_range(data, &prefix).map(|x| x.name.clone()));
+    }
+    candidates
+}
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct RegisterCompleter<'a> {
+    data: &'a BTreeMap<String, RegisterDesc>,
+    /// Field names of the selected register
+    fields: Vec<String>,
+}
+
+impl<'a> Completer for RegisterCompleter<'a> {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |x| x + 1);
+        Ok((start, complete(self.data, &self.fields, &line[start..pos])))
+    }
 }
 
 fn get_prompt<'a>(state: &'a TState<Elem>) -> &'a str {
@@ -40,34 +144,574 @@ fn get_prompt<'a>(state: &'a TState<Elem>) -> &'a str {
     }
 }
 
-pub fn run_tui(data: &BTreeMap<String, RegisterDesc>) -> io::Result<()> {
-    let mut fsm = Fsm::new(|prefix: &str| -> Vec<Elem> {
-        data.range(String::from(prefix)..)
-            .take_while(|x| x.0.starts_with(&prefix))
-            .map(|p| Elem(p.1, None))
+/// `:radix <radix>` sets default radix, `:radix <field> <radix>` overrides it
+/// for a single field
+fn set_radix(args: &str, opts: &mut RenderOptions) -> Result<(), String> {
+    let args: Vec<_> = args.split_whitespace().collect();
+
+    match args[..] {
+        [radix] => opts.radix = radix.parse()?,
+        [field, radix] => {
+            let radix: Radix = radix.parse()?;
+            opts.field_radix.insert(field.to_lowercase(), radix);
+        }
+        _ => return Err(String::from("Usage: :radix [field] dec|hex|bin|auto")),
+    }
+    Ok(())
+}
+
+fn run_command(cmd: &str, opts: &mut RenderOptions) -> Result<(), String> {
+    let (name, args) = cmd.split_once(' ').unwrap_or((cmd, ""));
+
+    match name {
+        "radix" => set_radix(args, opts),
+        _ => Err(format!("Unknown command: {}", name)),
+    }
+}
+
+/// `<old> vs <new>` compares two values of the selected register, values
+/// are expressions so `$ vs $ | 1` works as well
+fn diff(state: &TState<Elem>, input: &str, opts: &RenderOptions) -> Result<String, String> {
+    let TState::Selected(el) = state else {
+        return Err(String::from("No register selected"));
+    };
+    let input = input.to_lowercase();
+    let (old, new) = input.split_once(" vs ").ok_or("Usage: <old> vs <new>")?;
+
+    let old = el.eval(&old.parse()?)?;
+    let new = el.eval(&new.parse()?)?;
+    Ok(render_diff(el.0, old, new, opts))
+}
+
+/// Registers pinned with `:pin`, each keeping its own value
+#[derive(Default)]
+struct Workspace<'a>(Vec<Elem<'a>>);
+
+impl<'a> Workspace<'a> {
+    fn get(&self, name: &str) -> Option<&Elem<'a>> {
+        self.0.iter().find(|x| x.0.name.eq_ignore_ascii_case(name))
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Elem<'a>> {
+        self.0
+            .iter_mut()
+            .find(|x| x.0.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Pin `el` or, if its register is already pinned, replace the pinned copy
+    fn pin(&mut self, el: Elem<'a>) {
+        match self.get_mut(&el.0.name) {
+            Some(x) => *x = el,
+            None => self.0.push(el),
+        }
+    }
+
+    fn unpin(&mut self, name: &str) -> bool {
+        let len = self.0.len();
+        self.0.retain(|x| !x.0.name.eq_ignore_ascii_case(name));
+        self.0.len() != len
+    }
+
+    /// Keep the pinned copy of the selected register up to date
+    fn sync(&mut self, state: &TState<Elem<'a>>) {
+        if let TState::Selected(el) = state {
+            if let Some(x) = self.get_mut(&el.0.name) {
+                *x = el.clone();
+            }
+        }
+    }
+
+    fn names(&self) -> String {
+        let names: Vec<_> = self.0.iter().map(|x| x.0.name.as_str()).collect();
+        format!("Pinned: {}", names.join(", "))
+    }
+
+    fn render(&self, opts: &RenderOptions) -> String {
+        let regs: Vec<_> = self.0.iter().map(|x| x.render(opts)).collect();
+        regs.join("\n")
+    }
+}
+
+/// `:pin [reg...]` pins registers, the selected one by default, `:unpin
+/// [reg...]` removes them and `:ws` shows all pinned registers. Returns
+/// `None` for other commands.
+fn run_workspace_command<'a>(
+    cmd: &str,
+    data: &'a BTreeMap<String, RegisterDesc>,
+    state: &TState<Elem<'a>>,
+    ws: &mut Workspace<'a>,
+    opts: &RenderOptions,
+) -> Option<Result<Option<String>, String>> {
+    let (name, args) = cmd.split_once(' ').unwrap_or((cmd, ""));
+    let mut args: Vec<_> = args.split_whitespace().collect();
+
+    let selected = match state {
+        TState::Selected(el) => Some(el),
+        _ => None,
+    };
+    if args.is_empty() && matches!(name, "pin" | "unpin") {
+        match selected {
+            Some(el) => args.push(&el.0.name),
+            None => return Some(Err(format!("Usage: :{} <register>...", name))),
+        }
+    }
+
+    Some(match name {
+        "pin" => args
+            .iter()
+            .try_for_each(|&x| {
+                let el = match selected.filter(|el| el.0.name.eq_ignore_ascii_case(x)) {
+                    Some(el) => el.clone(),
+                    None if ws.get(x).is_some() => return Ok(()),
+                    None => Elem(
+                        find_register(data, x).ok_or("Unknown register")?,
+                        None,
+                        None,
+                    ),
+                };
+                ws.pin(el);
+                Ok(())
+            })
+            .map(|_| Some(ws.names())),
+        "unpin" => match args.iter().find(|&&x| !ws.unpin(x)) {
+            Some(x) => Err(format!("{} is not pinned", x)),
+            None => Ok(Some(ws.names())),
+        },
+        "ws" | "workspace" if ws.0.is_empty() => Err(String::from("No pinned registers")),
+        "ws" | "workspace" => Ok(Some(ws.render(opts))),
+        _ => return None,
+    })
+}
+
+/// Apply value, expression or field operation in `input` to pinned register
+fn apply_input(el: &mut Elem, input: &str) -> Result<(), String> {
+    match Event::from_str(input) {
+        Event::Number(x) => el.update(x),
+        Event::Field(op) => el.apply(&op)?,
+        Event::Expr(expr) => {
+            let x = el.eval(&expr)?;
+            el.update(x)
+        }
+        _ => return Err(format!("Expected a value or a field of {}", el.0.name)),
+    }
+    Ok(())
+}
+
+/// `field <name>` lists registers having the field with its bits
+fn find_field(index: &FieldIndex, name: &str) -> String {
+    let found = index.find(name);
+    if found.is_empty() {
+        return format!("No fields matching {}", name);
+    }
+    report::locations(&found, report::Format::Text)
+        .trim_end()
+        .to_string()
+}
+
+/// Values entered this session with their registers, oldest first
+type Log<'a> = Vec<(&'a RegisterDesc, u64)>;
+
+/// Ambiguous matches shown at once, the whole list if the terminal is tall
+/// enough
+fn page_size() -> usize {
+    terminal_size().map_or(20, |(_, Height(h))| (h as usize).saturating_sub(3).max(5))
+}
+
+/// `page` of numbered candidates with their width and field count
+fn candidates(v: &[Elem], page: usize, size: usize) -> String {
+    let pages = v.len().div_ceil(size);
+    let start = page * size;
+    let shown = &v[start.min(v.len())..(start + size).min(v.len())];
+    let index_width = (start + shown.len()).saturating_sub(1).to_string().len();
+    let name_width = shown.iter().map(|x| x.0.name.len()).max().unwrap_or(0);
+
+    let mut lines: Vec<_> = shown
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            let fields = x.0.fields.iter().filter(|x| !x.is_padding()).count();
+            format!(
+                "{:>index_width$}) {:<name_width$}  {} bits, {} fields",
+                start + i,
+                x.0.name,
+                x.0.bits,
+                fields
+            )
+        })
+        .collect();
+    if pages > 1 {
+        lines.push(format!(
+            "Page {}/{} of {} matches, :next and :prev turn pages, typing more narrows the list",
+            page + 1,
+            pages,
+            v.len()
+        ));
+    }
+    lines.join("\n")
+}
+
+/// `:next` and `:prev` page through ambiguous matches. Returns `None` for
+/// other commands.
+fn turn_page(cmd: &str, state: &TState<Elem>, page: &mut usize) -> Option<Result<(), String>> {
+    let forward = match cmd {
+        "next" => true,
+        "prev" => false,
+        _ => return None,
+    };
+    let TState::Ambiguous(_, v) = state else {
+        return Some(Err(String::from("No list of matches to page through")));
+    };
+
+    let pages = v.len().div_ceil(page_size());
+    match forward {
+        true if *page + 1 < pages => *page += 1,
+        false if *page > 0 => *page -= 1,
+        _ => return Some(Err(String::from("No more pages"))),
+    }
+    Some(Ok(()))
+}
+
+fn print_state(state: &TState<Elem>, opts: &RenderOptions, page: usize) {
+    match state {
+        TState::Selected(el) => println!("{}", el.render(opts)),
+        TState::Ambiguous(_, v) => println!("{}", candidates(v, page, page_size())),
+        TState::Empty | TState::Final => (),
+    }
+}
+
+/// `:history` lists values of the selected register, `:history all` values
+/// of all registers and `:history <n>` jumps back to n-th entry
+fn history<'a, F: Fn(&str) -> Vec<Elem<'a>>>(
+    args: &str,
+    fsm: &mut Fsm<Elem<'a>, F>,
+    log: &Log<'a>,
+) -> Result<Option<String>, String> {
+    let current = match &fsm.state {
+        TState::Selected(el) => Some(el.clone()),
+        _ => None,
+    };
+
+    if let Ok(i) = args.parse::<usize>() {
+        let &(reg, value) = log
+            .get(i)
+            .ok_or_else(|| format!("No history entry {}", i))?;
+        let prev = current.filter(|x| x.0.name == reg.name).and_then(|x| x.1);
+        fsm.set_state(TState::Selected(Elem(reg, Some(value), prev)));
+        return Ok(None);
+    }
+
+    let all = match args {
+        "" => current.is_none(),
+        "all" => true,
+        _ => return Err(String::from("Usage: :history [all|<n>]")),
+    };
+
+    let mut out = String::new();
+    for (i, (reg, value)) in log.iter().enumerate() {
+        if all {
+            out.push_str(&format!("{}) {} {:#x}\n", i, reg.name, value));
+        } else if current.as_ref().is_some_and(|x| x.0.name == reg.name) {
+            out.push_str(&format!("{}) {:#x}\n", i, value));
+        }
+    }
+    Ok(Some(out.trim_end().to_string()))
+}
+
+/// Commands changing the state: `:undo`, `:redo` and `:history`. Returns
+/// `None` for other commands.
+fn run_session_command<'a, F: Fn(&str) -> Vec<Elem<'a>>>(
+    cmd: &str,
+    fsm: &mut Fsm<Elem<'a>, F>,
+    log: &Log<'a>,
+) -> Option<Result<Option<String>, String>> {
+    let (name, args) = cmd.split_once(' ').unwrap_or((cmd, ""));
+
+    Some(match name {
+        "undo" if fsm.undo() => Ok(None),
+        "undo" => Err(String::from("Nothing to undo")),
+        "redo" if fsm.redo() => Ok(None),
+        "redo" => Err(String::from("Nothing to redo")),
+        "history" => history(args.trim(), fsm, log),
+        _ => return None,
+    })
+}
+
+pub fn run_tui<'a>(
+    data: &'a BTreeMap<String, RegisterDesc>,
+    index: &FieldIndex<'a>,
+    opts: &RenderOptions,
+) -> io::Result<()> {
+    let mut opts = opts.clone();
+    let profile = opts.features.clone().filter(|_| opts.hide_absent);
+    let is_present = |reg: &RegisterDesc| profile.as_ref().is_none_or(|x| x.has_all(&reg.features));
+    let ws = RefCell::new(Workspace::default());
+    /* Pinned registers are selected with their values */
+    let elem = |reg: &'a RegisterDesc| match ws.borrow().get(&reg.name) {
+        Some(el) => Elem(reg, el.1, el.2),
+        None => Elem(reg, None, None),
+    };
+    let mut fsm = Fsm::new(|query: &str| -> Vec<Elem> {
+        /* Generic S<op0>_<op1>_C<n>_C<m>_<op2> name resolves to a single register */
+        let found = match query.parse::<Encoding>() {
+            Ok(_) => find_register(data, query).into_iter().collect(),
+            Err(_) => query
+                .parse::<Query>()
+                .map(|x| search(data, index, &x))
+                .unwrap_or_default(),
+        };
+        found
+            .into_iter()
+            .filter(|reg| is_present(reg))
+            .map(elem)
             .collect()
     });
 
+    let mut editor: Editor<RegisterCompleter, DefaultHistory> =
+        Editor::new().map_err(io::Error::other)?;
+    editor.set_helper(Some(RegisterCompleter {
+        data,
+        fields: Vec::new(),
+    }));
+    /* History file doesn't exist on the first run */
+    let _ = editor.load_history(&history_path());
+
+    let mut log = Log::new();
+    /* Page of the ambiguous matches list */
+    let mut page = 0;
+
     println!("Enter register names:");
     loop {
-        print!("{}> ", get_prompt(&fsm.state));
-        io::stdout().flush()?;
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+        ws.borrow_mut().sync(&fsm.state);
+
+        let fields = match &fsm.state {
+            TState::Selected(el) => el.0.fields.iter().filter_map(|x| x.name.clone()).collect(),
+            _ => Vec::new(),
+        };
+        if let Some(helper) = editor.helper_mut() {
+            helper.fields = fields;
+        }
+
+        let input = match editor.readline(&format!("{}> ", get_prompt(&fsm.state))) {
+            Ok(line) => line,
+            Err(ReadlineError::Eof | ReadlineError::Interrupted) => break,
+            Err(e) => return Err(io::Error::other(e)),
+        };
+        if !input.trim().is_empty() {
+            let _ = editor.add_history_entry(input.trim());
+        }
+
+        if let Some(cmd) = input.trim().strip_prefix(':') {
+            if let Some(result) = turn_page(cmd, &fsm.state, &mut page) {
+                match result {
+                    Ok(()) => print_state(&fsm.state, &opts, page),
+                    Err(e) => println!("{}", e),
+                }
+                continue;
+            }
+            page = 0;
+
+            let result = run_session_command(cmd, &mut fsm, &log)
+                .or_else(|| {
+                    run_workspace_command(cmd, data, &fsm.state, &mut ws.borrow_mut(), &opts)
+                })
+                .unwrap_or_else(|| run_command(cmd, &mut opts).map(|_| None));
+            match result {
+                Ok(Some(out)) => println!("{}", out),
+                Ok(None) => print_state(&fsm.state, &opts, page),
+                Err(e) => println!("{}", e),
+            }
+            continue;
+        }
+
+        if let Some(name) = input.trim().strip_prefix("field ") {
+            println!("{}", find_field(index, name.trim()));
+            continue;
+        }
+
+        if input.to_lowercase().contains(" vs ") {
+            match diff(&fsm.state, &input, &opts) {
+                Ok(out) => print!("{}", out),
+                Err(e) => println!("{}", e),
+            }
+            continue;
+        }
+
+        /* `<register> <input>` changes a pinned register in place */
+        let pinned = input
+            .trim()
+            .split_once(char::is_whitespace)
+            .and_then(|(name, rest)| Some((ws.borrow().get(name)?.clone(), rest)));
+        if let Some((mut el, rest)) = pinned {
+            match apply_input(&mut el, rest) {
+                Ok(()) => {
+                    if let TState::Selected(x) = &fsm.state {
+                        if x.0.name == el.0.name {
+                            fsm.set_state(TState::Selected(el.clone()));
+                        }
+                    }
+                    if let Some(value) = el.1 {
+                        log.push((el.0, value));
+                    }
+                    println!("{}", el.render(&opts));
+                    ws.borrow_mut().pin(el);
+                }
+                Err(e) => println!("{}", e),
+            }
+            continue;
+        }
+
         let event = Event::from_str(&input);
 
+        page = 0;
         fsm.next(event);
+        if let Some(e) = &fsm.error {
+            println!("{}", e);
+            continue;
+        }
 
-        match &&fsm.state {
-            TState::Selected(el) => println!("{}", el),
-            TState::Ambiguous(_, v) => {
-                for (i, x) in v.iter().enumerate() {
-                    println!("{}) {}", i, x.0.name)
-                }
+        match &fsm.state {
+            /* Repeated value is logged once */
+            TState::Selected(Elem(reg, Some(value), _))
+                if !log
+                    .last()
+                    .is_some_and(|(r, v)| r.name == reg.name && v == value) =>
+            {
+                log.push((reg, *value));
             }
-            TState::Empty => (),
             TState::Final => break,
+            _ => (),
         }
+        print_state(&fsm.state, &opts, page);
+    }
+
+    /* Failing to keep history is not worth an error on exit */
+    if let Err(e) = editor.save_history(&history_path()) {
+        eprintln!("Can't save history to {}: {}", history_path().display(), e);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use asl_parser::{meta::apply_meta, parse_registers};
+
+    use crate::{
+        prefix_fsm::{FieldOp, Item, TState},
+        tui_fsm::{apply_input, candidates, complete, Elem, Workspace},
+    };
+
+    #[test]
+    fn test_complete() {
+        let data = parse_registers(
+            "__register 64 { 0:0 M } SCTLR_EL1;
+             __register 64 { 0:0 M } SCTLR_EL2;
+             __register 64 { 0:0 M } TCR_EL1;",
+        );
+        let fields = vec![
+            String::from("SSBS"),
+            String::from("SPAN"),
+            String::from("M"),
+        ];
+
+        assert_eq!(complete(&data, &[], "sctlr"), ["SCTLR_EL1", "SCTLR_EL2"]);
+        assert_eq!(
+            complete(&data, &fields, "s"),
+            ["SSBS", "SPAN", "SCTLR_EL1", "SCTLR_EL2"]
+        );
+        assert_eq!(complete(&data, &fields, "sp"), ["SPAN"]);
+        assert_eq!(complete(&data, &fields, ""), ["SSBS", "SPAN", "M"]);
+        assert!(complete(&data, &[], "x").is_empty());
+    }
+
+    #[test]
+    fn test_workspace() {
+        let data = parse_registers(
+            "__register 64 { 2:2 C, 0:0 M } SCTLR_EL1;
+             __register 64 { 15:14 TG0 } TCR_EL1;",
+        );
+        let mut ws = Workspace::default();
+        ws.pin(Elem(&data["sctlr_el1"], Some(1), None));
+        ws.pin(Elem(&data["tcr_el1"], None, None));
+        assert_eq!(ws.names(), "Pinned: SCTLR_EL1, TCR_EL1");
+
+        let mut el = ws.get("tcr_el1").unwrap().clone();
+        apply_input(&mut el, "TG0=0b10").unwrap();
+        assert_eq!(el.1, Some(0x8000));
+        ws.pin(el);
+        assert_eq!(ws.get("TCR_EL1").unwrap().1, Some(0x8000));
+
+        let mut el = ws.get("sctlr_el1").unwrap().clone();
+        apply_input(&mut el, "$ | 4").unwrap();
+        assert_eq!(el.1, Some(5));
+        assert!(apply_input(&mut el, "foo").is_err());
+
+        /* Selection updates only its own pinned copy */
+        ws.sync(&TState::Selected(el));
+        assert_eq!(ws.get("sctlr_el1").unwrap().1, Some(5));
+        assert_eq!(ws.get("tcr_el1").unwrap().1, Some(0x8000));
+
+        assert!(ws.unpin("TCR_EL1"));
+        assert!(!ws.unpin("tcr_el1"));
+        assert_eq!(ws.names(), "Pinned: SCTLR_EL1");
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut data =
+            parse_registers("__register 64 { 23:23 SPAN, 15:14 TG0, 2:2 C, 0:0 M } REG;");
+        apply_meta(&mut data, r#"__values REG.TG0 { 0b10 = "16KB" };"#).unwrap();
+        let mut el = Elem(&data["reg"], None, None);
+
+        /* Value starts from 0 */
+        el.apply(&FieldOp::Set(String::from("23"))).unwrap();
+        assert_eq!(el.1, Some(0x800000));
+        el.apply(&FieldOp::Set(String::from("15:14"))).unwrap();
+        assert_eq!(el.1, Some(0x80c000));
+        el.apply(&FieldOp::Clear(String::from("23"))).unwrap();
+        assert_eq!(el.1, Some(0xc000));
+        el.apply(&FieldOp::Clear(String::from("15:14"))).unwrap();
+        assert_eq!(el.1, Some(0));
+
+        el.apply(&FieldOp::Toggle(String::from("m"))).unwrap();
+        el.apply(&FieldOp::Toggle(String::from("C"))).unwrap();
+        assert_eq!(el.1, Some(5));
+        el.apply(&FieldOp::Toggle(String::from("M"))).unwrap();
+        assert_eq!((el.1, el.2), (Some(4), Some(5)));
+
+        el.apply(&FieldOp::Assign(String::from("tg0"), String::from("16kb")))
+            .unwrap();
+        assert_eq!(el.1, Some(0x8004));
+        el.apply(&FieldOp::Assign(String::from("TG0"), String::from("1")))
+            .unwrap();
+        assert_eq!(el.1, Some(0x4004));
+
+        assert_eq!(
+            el.apply(&FieldOp::Assign(String::from("TG0"), String::from("4"))),
+            Err(String::from("Value 0x4 doesn't fit into TG0 bits 15..14"))
+        );
+        assert_eq!(
+            el.apply(&FieldOp::Clear(String::from("X"))),
+            Err(String::from("Unknown field: X"))
+        );
+        assert_eq!(el.1, Some(0x4004));
+    }
+
+    #[test]
+    fn test_candidates() {
+        let data = parse_registers(
+            "__register 64 { 2:2 C, 0:0 M } SCTLR_EL1;
+             __register 64 { 0:0 M } SCTLR_EL2;
+             __register 32 { 0:0 M } SCTLR;",
+        );
+        let v: Vec<_> = data.values().map(|x| Elem(x, None, None)).collect();
+
+        assert_eq!(
+            candidates(&v, 0, 2),
+            "0) SCTLR      32 bits, 1 fields\n\
+             1) SCTLR_EL1  64 bits, 2 fields\n\
+             Page 1/2 of 3 matches, :next and :prev turn pages, typing more narrows the list"
+        );
+        assert_eq!(candidates(&v, 1, 2), "2) SCTLR_EL2  64 bits, 1 fields\nPage 2/2 of 3 matches, :next and :prev turn pages, typing more narrows the list");
+        assert_eq!(candidates(&v[1..], 0, 5).lines().count(), 2);
+    }
+}